@@ -1,18 +1,28 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::consts::{OUT_OF_BOUNDS_X, OUT_OF_BOUNDS_Y, WINDOW_WIDTH};
-use crate::{AppState, BulletCollisionEvent, ExplosionEvent, ExplosionType};
+use crate::animation::{Animation, AnimationLibrary, Clip};
+use crate::consts::{GROUND_Y, OUT_OF_BOUNDS_X, OUT_OF_BOUNDS_Y, WINDOW_WIDTH};
+use crate::menu::game_time_running;
+use crate::particle::{Effect, ParticleEmitter};
+use crate::rules::{random_spawns_enabled, Difficulty};
+use crate::session::GameSession;
+use crate::{AircraftEscapeEvent, AppState, BulletCollisionEvent, ExplosionEvent, ExplosionType};
 use rand::Rng;
 
 pub const AIRCRAFT_SPEED: f32 = 80.;
 pub const AIRCRAFT_SCALE: f32 = 0.3;
+const WRECK_COLOR: Color = Color::rgb(0.25, 0.22, 0.2);
+/// Pixels per second squared
+const WRECK_GRAVITY: f32 = 300.;
 pub const AIRCRAFT_SPAWN_PROBABILITY: f32 = 0.008;
 pub const SPAWN_LEFT_X: f32 = -WINDOW_WIDTH / 2.0 - 40.;
 pub const SPAWN_RIGHT_X: f32 = WINDOW_WIDTH / 2.0 + 40.;
 pub const SPAWN_Y_MIN: f32 = 100.;
 pub const SPAWN_Y_MAX: f32 = 350.;
-const PARATROOPER_STICK_SIZE: usize = 5; // Max number of paratroopers dropped per aircraft
+pub const PARATROOPER_STICK_SIZE: usize = 5; // Max number of paratroopers dropped per aircraft
+/// Propeller hub relative to the center of the unscaled 412 x 114 sprite, heading right
+const PROPELLER_OFFSET: Vec2 = Vec2::new(200., -8.);
 
 #[derive(Component)]
 pub struct Aircraft {
@@ -26,54 +36,112 @@ impl Default for Aircraft {
     }
 }
 
+/// Paratroopers on board each randomly spawned aircraft
 #[derive(Resource)]
-struct AircraftTextures {
+pub struct StickSize(pub usize);
+
+impl Default for StickSize {
+    fn default() -> StickSize {
+        StickSize(PARATROOPER_STICK_SIZE)
+    }
+}
+
+/// Paratroopers jump one at a time, starting over `x`, instead of at random
+#[derive(Component)]
+pub struct DropZone {
+    pub x: f32,
+    pub dropped: usize,
+}
+
+/// Horizontal distance between jumpers in a stick dropped over a `DropZone`
+const STICK_SPACING: f32 = 30.;
+
+impl DropZone {
+    pub fn new(x: f32) -> DropZone {
+        DropZone { x, dropped: 0 }
+    }
+
+    /// Has an aircraft at `aircraft_x` flying along `heading` reached the next jump point?
+    pub fn is_over(&self, aircraft_x: f32, heading: f32) -> bool {
+        let jump_x = self.x + heading * self.dropped as f32 * STICK_SPACING;
+        heading * (aircraft_x - jump_x) >= 0.
+    }
+}
+
+#[derive(Resource)]
+pub struct AircraftTextures {
     image_handle: Handle<Image>,
 }
 
-fn spawn_aircraft_system(mut commands: Commands, aircraft_textures: Res<AircraftTextures>) {
+fn spawn_aircraft_system(
+    mut commands: Commands,
+    aircraft_textures: Res<AircraftTextures>,
+    difficulty: Res<Difficulty>,
+    stick_size: Res<StickSize>,
+) {
     let mut rng = rand::thread_rng();
-    if rng.gen_range(0.0..1.0) < AIRCRAFT_SPAWN_PROBABILITY {
+    if rng.gen_range(0.0..1.0) < AIRCRAFT_SPAWN_PROBABILITY * difficulty.spawn_rate() {
         let y = rng.gen_range(SPAWN_Y_MIN..SPAWN_Y_MAX);
         let heading_right = rng.gen_bool(0.5);
         let speed = rng.gen_range(0.8..1.3) * AIRCRAFT_SPEED;
-        let multiplier = if heading_right { 1.0 } else { -1.0 };
-        let velocity = multiplier * speed;
-        let transform = if heading_right {
-            Transform::from_translation(Vec3::new(SPAWN_LEFT_X, y, 3.))
-        } else {
-            Transform::from_translation(Vec3::new(SPAWN_RIGHT_X, y, 3.))
-        }
-        .with_scale(Vec3::new(AIRCRAFT_SCALE, AIRCRAFT_SCALE, 1.));
-
-        let sprite_bundle = SpriteBundle {
-            // 412 x 114 pixels. 0.3 scale.
-            texture: aircraft_textures.image_handle.clone(),
-            sprite: Sprite {
-                flip_x: !heading_right,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-
-        commands
-            .spawn(sprite_bundle)
-            .insert(transform)
-            .insert(RigidBody::Dynamic)
-            .insert(Collider::cuboid(412. / 2.0, 114. / 2.0))
-            .insert(Sensor)
-            .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC)
-            .insert(CollisionGroups::new(
-                Group::GROUP_3,
-                Group::GROUP_2 | Group::GROUP_3 | Group::GROUP_4,
-            ))
-            .insert(LockedAxes::TRANSLATION_LOCKED_Y)
-            .insert(Velocity {
-                linvel: Vec2::new(velocity, 0.),
-                angvel: 0.0,
-            })
-            .insert(Aircraft::default());
+        spawn_aircraft(
+            &mut commands,
+            &aircraft_textures,
+            heading_right,
+            y,
+            speed,
+            stick_size.0,
+        );
+    }
+}
+
+/// Spawn an aircraft entering from the left or right edge at altitude `y`
+pub fn spawn_aircraft(
+    commands: &mut Commands,
+    aircraft_textures: &AircraftTextures,
+    heading_right: bool,
+    y: f32,
+    speed: f32,
+    paratroopers: usize,
+) -> Entity {
+    let multiplier = if heading_right { 1.0 } else { -1.0 };
+    let velocity = multiplier * speed;
+    let transform = if heading_right {
+        Transform::from_translation(Vec3::new(SPAWN_LEFT_X, y, 3.))
+    } else {
+        Transform::from_translation(Vec3::new(SPAWN_RIGHT_X, y, 3.))
     }
+    .with_scale(Vec3::new(AIRCRAFT_SCALE, AIRCRAFT_SCALE, 1.));
+
+    let sprite_bundle = SpriteBundle {
+        // 412 x 114 pixels. 0.3 scale.
+        texture: aircraft_textures.image_handle.clone(),
+        sprite: Sprite {
+            flip_x: !heading_right,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    commands
+        .spawn(sprite_bundle)
+        .insert(transform)
+        .insert(RigidBody::Dynamic)
+        .insert(Collider::cuboid(412. / 2.0, 114. / 2.0))
+        .insert(Sensor)
+        .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC)
+        .insert(CollisionGroups::new(
+            Group::GROUP_3,
+            Group::GROUP_2 | Group::GROUP_3 | Group::GROUP_4,
+        ))
+        .insert(LockedAxes::TRANSLATION_LOCKED_Y)
+        .insert(Velocity {
+            linvel: Vec2::new(velocity, 0.),
+            angvel: 0.0,
+        })
+        .insert(Aircraft { paratroopers })
+        .insert(GameSession)
+        .id()
 }
 
 fn setup_aircraft_system(mut commands: Commands, asset_server: ResMut<AssetServer>) {
@@ -82,38 +150,111 @@ fn setup_aircraft_system(mut commands: Commands, asset_server: ResMut<AssetServe
     });
 }
 
-fn despawn_all_aircraft(mut commands: Commands, query: Query<Entity, With<Aircraft>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn();
+/// Spin a propeller on the nose of each new aircraft
+fn attach_propellers(
+    mut commands: Commands,
+    animations: Res<AnimationLibrary>,
+    query: Query<(Entity, &Velocity), Added<Aircraft>>,
+) {
+    for (entity, velocity) in query.iter() {
+        let heading = velocity.linvel.x.signum();
+        let transform = Transform::from_xyz(heading * PROPELLER_OFFSET.x, PROPELLER_OFFSET.y, 0.1);
+        let propeller = commands
+            .spawn(animations.sprite_sheet(Clip::Propeller, transform))
+            .insert(Animation::new(Clip::Propeller))
+            .id();
+        commands.entity(entity).add_child(propeller);
     }
 }
 
+/// Remove aircraft that left the screen, reporting any paratroopers still on board
 fn despawn_escaped_aircraft(
     mut commands: Commands,
-    query: Query<(Entity, &Transform), With<Aircraft>>,
+    query: Query<(Entity, &Aircraft, &Transform)>,
+    mut event_writer: EventWriter<AircraftEscapeEvent>,
 ) {
-    for (entity, transform) in query.iter() {
+    for (entity, aircraft, transform) in query.iter() {
         if transform.translation.x.abs() > OUT_OF_BOUNDS_X
             || transform.translation.y.abs() > OUT_OF_BOUNDS_Y
         {
+            event_writer.send(AircraftEscapeEvent {
+                paratroopers: aircraft.paratroopers,
+            });
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+/// Burnt-out hull of a shot down aircraft or bomber, tumbling to the ground trailing smoke.
+/// A single hit brings any plane down, so the wreck is where damage shows.
+#[derive(Component)]
+pub struct Wreck {
+    velocity: Vec2,
+    /// Radians per second
+    spin: f32,
+}
+
+/// What a shot down aircraft leaves its wreck
+type WreckSource<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Handle<Image>,
+    &'a Sprite,
+    &'a Velocity,
+);
+
 fn bullet_collision_system(
     mut commands: Commands,
-    aircraft_query: Query<(Entity, &Transform), With<Aircraft>>,
+    aircraft_query: Query<WreckSource, With<Aircraft>>,
     mut event_reader: EventReader<BulletCollisionEvent>,
     mut event_writer: EventWriter<ExplosionEvent>,
 ) {
+    let mut rng = rand::thread_rng();
     for event in event_reader.read() {
-        if let Ok((aircraft_entity, aircraft_transform)) = aircraft_query.get(event.target_entity) {
+        if let Ok((aircraft_entity, aircraft_transform, texture, sprite, velocity)) =
+            aircraft_query.get(event.target_entity)
+        {
             event_writer.send(ExplosionEvent {
                 transform: (*aircraft_transform).with_scale(Vec3::ONE),
                 explosion_type: ExplosionType::Aircraft,
             });
             commands.entity(aircraft_entity).despawn_recursive();
+
+            // Nose down in the direction of flight
+            let heading = velocity.linvel.x.signum();
+            commands
+                .spawn(SpriteBundle {
+                    texture: texture.clone(),
+                    sprite: Sprite {
+                        color: WRECK_COLOR,
+                        flip_x: sprite.flip_x,
+                        ..default()
+                    },
+                    transform: *aircraft_transform,
+                    ..default()
+                })
+                .insert(Wreck {
+                    velocity: Vec2::new(0.5 * velocity.linvel.x, rng.gen_range(0.0..60.)),
+                    spin: -heading * rng.gen_range(0.5..1.5),
+                })
+                .insert(ParticleEmitter::new(Effect::Smoke))
+                .insert(GameSession);
+        }
+    }
+}
+
+fn fall_wrecks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut wreck_query: Query<(Entity, &mut Wreck, &mut Transform)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut wreck, mut transform) in wreck_query.iter_mut() {
+        wreck.velocity.y -= WRECK_GRAVITY * dt;
+        transform.translation += (wreck.velocity * dt).extend(0.);
+        transform.rotate_z(wreck.spin * dt);
+        if transform.translation.y < GROUND_Y || transform.translation.x.abs() > OUT_OF_BOUNDS_X {
+            commands.entity(entity).despawn();
         }
     }
 }
@@ -122,17 +263,18 @@ pub struct AircraftPlugin;
 
 impl Plugin for AircraftPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_aircraft_system)
+        app.init_resource::<StickSize>()
+            .add_systems(Startup, setup_aircraft_system)
             .add_systems(
                 Update,
                 (
-                    spawn_aircraft_system,
+                    spawn_aircraft_system.run_if(random_spawns_enabled),
                     bullet_collision_system,
                     despawn_escaped_aircraft,
+                    attach_propellers,
+                    fall_wrecks.run_if(game_time_running),
                 )
                     .run_if(in_state(AppState::InGame)),
-            )
-            .add_systems(OnEnter(AppState::MainMenu), despawn_all_aircraft)
-            .add_systems(OnEnter(AppState::GameOver), despawn_all_aircraft);
+            );
     }
 }
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::f32::consts::FRAC_PI_4;
+use std::fs;
+
+use crate::menu::game_time_running;
+
+const ANIMATIONS_PATH: &str = "assets/animations.ron";
+
+/// How a clip runs through its frames
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+    Loop,
+    /// Stop on the last frame
+    Once,
+    /// Forward, then back, and around again
+    PingPong,
+}
+
+/// Where a sheet's pixels come from
+#[derive(Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+enum SheetImage {
+    /// Path under `assets`
+    File(String),
+    /// Drawn at startup to fit the sheet, as there is no art for it
+    Propeller,
+}
+
+/// Image cut into equal frames on a grid, numbered left to right, top to bottom
+#[derive(Deserialize, Clone, Debug)]
+struct Sheet {
+    image: SheetImage,
+    frame_size: Vec2,
+    columns: usize,
+    rows: usize,
+}
+
+/// Everything there is to know about playing a clip, as read from `assets/animations.ron`
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClipData {
+    sheet: Sheet,
+    /// Sheet frame indices in play order
+    frames: Vec<usize>,
+    fps: f32,
+    mode: PlayMode,
+    /// Remove the entity once a `PlayMode::Once` clip ends
+    #[serde(default)]
+    despawn_on_finish: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Clip {
+    AirExplosion,
+    GroundExplosion,
+    Gib,
+    BombSpin,
+    Propeller,
+}
+
+impl Clip {
+    const ALL: [Clip; 5] = [
+        Clip::AirExplosion,
+        Clip::GroundExplosion,
+        Clip::Gib,
+        Clip::BombSpin,
+        Clip::Propeller,
+    ];
+}
+
+/// Read every clip. The game cannot draw without them, so a missing or broken file is fatal.
+fn load_clips() -> HashMap<Clip, ClipData> {
+    let clips: HashMap<Clip, ClipData> = fs::read_to_string(ANIMATIONS_PATH)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| ron::from_str(&contents).map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| panic!("Could not load {}: {}", ANIMATIONS_PATH, error));
+    for clip in Clip::ALL {
+        let Some(data) = clips.get(&clip) else {
+            panic!("{} has no {:?} clip", ANIMATIONS_PATH, clip);
+        };
+        let frame_count = data.sheet.columns * data.sheet.rows;
+        if data.frames.is_empty() || data.frames.iter().any(|&frame| frame >= frame_count) {
+            panic!("{:?} in {} has frames off its sheet", clip, ANIMATIONS_PATH);
+        }
+    }
+    clips
+}
+
+/// Plays a clip on a sprite sheet
+#[derive(Component)]
+pub struct Animation {
+    clip: Clip,
+    /// Position in the clip's frame list
+    step: usize,
+    /// Direction through a ping-pong clip
+    forward: bool,
+    timer: Timer,
+    /// New clip, so the sheet and frame rate need setting from its data
+    clip_changed: bool,
+}
+
+impl Animation {
+    pub fn new(clip: Clip) -> Animation {
+        Animation {
+            clip,
+            step: 0,
+            forward: true,
+            timer: Timer::default(),
+            clip_changed: true,
+        }
+    }
+
+    /// Step to the next frame. Returns false once a `PlayMode::Once` clip has ended.
+    fn advance(&mut self, data: &ClipData) -> bool {
+        let last = data.frames.len() - 1;
+        match data.mode {
+            PlayMode::Loop => self.step = (self.step + 1) % data.frames.len(),
+            PlayMode::Once if self.step < last => self.step += 1,
+            PlayMode::Once => return false,
+            PlayMode::PingPong if last == 0 => (),
+            PlayMode::PingPong => {
+                if (self.forward && self.step == last) || (!self.forward && self.step == 0) {
+                    self.forward = !self.forward;
+                }
+                self.step = if self.forward {
+                    self.step + 1
+                } else {
+                    self.step - 1
+                };
+            }
+        }
+        true
+    }
+}
+
+/// Clip data, image and atlas layout for every clip
+#[derive(Resource)]
+pub struct AnimationLibrary {
+    clips: HashMap<Clip, ClipData>,
+    sheets: HashMap<Clip, (Handle<Image>, Handle<TextureAtlasLayout>)>,
+}
+
+impl AnimationLibrary {
+    /// Sprite showing the first frame of `clip`, to go with `Animation::new(clip)`
+    pub fn sprite_sheet(&self, clip: Clip, transform: Transform) -> SpriteSheetBundle {
+        let (texture, layout) = self.sheets[&clip].clone();
+        SpriteSheetBundle {
+            atlas: TextureAtlas {
+                layout,
+                index: self.clips[&clip].frames[0],
+            },
+            texture,
+            transform,
+            ..default()
+        }
+    }
+}
+
+/// Propeller blur seen side on: a blade sweeping from full length to edge on and back, one
+/// frame per sheet column
+fn propeller_image(sheet: &Sheet) -> Image {
+    let frame_width = sheet.frame_size.x as usize;
+    let height = sheet.frame_size.y as usize;
+    let width = frame_width * sheet.columns;
+    let mut data = vec![0u8; width * height * 4];
+    for frame in 0..sheet.columns {
+        let reach = (frame as f32 * FRAC_PI_4).cos().abs() * height as f32 / 2.;
+        for y in 0..height {
+            let offset = (y as f32 + 0.5 - height as f32 / 2.).abs();
+            let hub = offset < 5.;
+            if offset > reach && !hub {
+                continue;
+            }
+            for x in 3..frame_width.saturating_sub(3) {
+                let pixel = 4 * (y * width + frame * frame_width + x);
+                let alpha = if hub { 255 } else { 150 };
+                data[pixel..pixel + 4].copy_from_slice(&[40, 40, 40, alpha]);
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn setup_animations(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let clips = load_clips();
+    let mut loaded: HashMap<SheetImage, (Handle<Image>, Handle<TextureAtlasLayout>)> =
+        HashMap::new();
+    let mut sheets = HashMap::new();
+    for (&clip, data) in clips.iter() {
+        let sheet = &data.sheet;
+        let handles = loaded
+            .entry(sheet.image.clone())
+            .or_insert_with(|| {
+                let image = match &sheet.image {
+                    SheetImage::File(path) => asset_server.load(path.clone()),
+                    SheetImage::Propeller => images.add(propeller_image(sheet)),
+                };
+                let layout = texture_atlases.add(TextureAtlasLayout::from_grid(
+                    sheet.frame_size,
+                    sheet.columns,
+                    sheet.rows,
+                    None,
+                    None,
+                ));
+                (image, layout)
+            })
+            .clone();
+        sheets.insert(clip, handles);
+    }
+    commands.insert_resource(AnimationLibrary { clips, sheets });
+}
+
+fn animate_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    library: Res<AnimationLibrary>,
+    mut query: Query<(
+        Entity,
+        &mut Animation,
+        &mut TextureAtlas,
+        &mut Handle<Image>,
+    )>,
+) {
+    for (entity, mut animation, mut atlas, mut texture) in query.iter_mut() {
+        let data = &library.clips[&animation.clip];
+        if animation.clip_changed {
+            animation.clip_changed = false;
+            let (new_texture, new_layout) = library.sheets[&animation.clip].clone();
+            *texture = new_texture;
+            atlas.layout = new_layout;
+            animation.timer = Timer::from_seconds(1. / data.fps, TimerMode::Repeating);
+        }
+
+        animation.timer.tick(time.delta());
+        for _ in 0..animation.timer.times_finished_this_tick() {
+            if !animation.advance(data) {
+                if data.despawn_on_finish {
+                    commands.entity(entity).despawn_recursive();
+                }
+                break;
+            }
+        }
+        atlas.index = data.frames[animation.step];
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_animations)
+            .add_systems(Update, animate_system.run_if(game_time_running));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::gun::GunBase;
+use crate::gun::{GunBase, GunDestroyed};
 use crate::paratrooper::{Paratrooper, ParatrooperState};
 use crate::{AppState, LandingEvent};
 use bevy::prelude::*;
@@ -22,29 +22,47 @@ fn enable_assault_system(
     }
 }
 
-/// Walk towards the gun
-fn assault_movement_system(mut query: Query<(&Paratrooper, &Transform, &mut Velocity)>) {
+/// The x of the gun still standing nearest to `x`
+fn nearest_gun_x(
+    x: f32,
+    gun_base_query: &Query<&Transform, (With<GunBase>, Without<GunDestroyed>)>,
+) -> Option<f32> {
+    gun_base_query
+        .iter()
+        .map(|gun_transform| gun_transform.translation.x)
+        .min_by(|a, b| (a - x).abs().total_cmp(&(b - x).abs()))
+}
+
+/// Walk towards the nearest gun still standing
+fn assault_movement_system(
+    mut query: Query<(&Paratrooper, &Transform, &mut Velocity)>,
+    gun_base_query: Query<&Transform, (With<GunBase>, Without<GunDestroyed>)>,
+) {
     for (_paratrooper, transform, mut velocity) in query
         .iter_mut()
         .filter(|(p, _, _)| p.state == ParatrooperState::Assault)
     {
+        let x = transform.translation.x;
+        let Some(gun_x) = nearest_gun_x(x, &gun_base_query) else {
+            continue;
+        };
         // Move towards gun.
-        let heading = -1.0 * transform.translation.x.signum();
+        let heading = (gun_x - x).signum();
         //velocity.linvel = Vec2::new(heading * PARATROOPER_WALK_SPEED, 0.0).into();
         velocity.linvel.x = heading * PARATROOPER_WALK_SPEED;
     }
 }
 
-/// Jump paratrooper further from gun when colliding
+/// Jump paratrooper further from the nearest gun when colliding
 fn assault_collision_system(
     mut collision_events: EventReader<CollisionEvent>,
     mut paratroopers: Query<(&Paratrooper, &Transform, &mut Velocity)>,
     gun_base_query: Query<Entity, With<GunBase>>,
+    standing_gun_query: Query<&Transform, (With<GunBase>, Without<GunDestroyed>)>,
 ) {
-    let gun_base_entity = gun_base_query.single();
     for collision_event in collision_events.read() {
         if let CollisionEvent::Started(entity1, entity2, _) = collision_event {
-            if gun_base_entity == *entity1 || gun_base_entity == *entity2 {
+            if gun_base_query.contains(*entity1) || gun_base_query.contains(*entity2) {
                 info!("Gun base collision.");
             } else {
                 // Paratrooper <-> Paratrooper
@@ -53,8 +71,11 @@ fn assault_collision_system(
                 {
                     let x1 = t1.translation.x;
                     let x2 = t2.translation.x;
+                    let distance = |x: f32| {
+                        nearest_gun_x(x, &standing_gun_query).map_or(0., |gun_x| (x - gun_x).abs())
+                    };
                     if (x1 - x2).abs() < 40. {
-                        let mut velocity = if x1.abs() > x2.abs() { v1 } else { v2 };
+                        let mut velocity = if distance(x1) > distance(x2) { v1 } else { v2 };
                         velocity.linvel.y = 75.;
                     }
                 }
@@ -1,12 +1,28 @@
 use crate::bomber::Bomb;
+use crate::consts;
+use crate::menu::game_time_running;
+use crate::mixer::{Channel, Mixer, MixerChannel};
+use crate::session::GameSession;
+use crate::settings::GameSettings;
+use crate::voice::{SoundCategory, Voice};
 use crate::{
     AppState, BulletCollisionEvent, CollisionType, ExplosionEvent, ExplosionType, GibEvent,
     GunExplosionEvent, GunshotEvent,
 };
-use bevy::audio::{AudioSink, PlaybackMode, Volume};
+use bevy::audio::{
+    AudioSink, AudioSinkPlayback, PlaybackMode, SpatialAudioSink, SpatialListener, SpatialScale,
+    Volume,
+};
 use bevy::prelude::*;
 use rand::seq::SliceRandom;
 
+const GUNSHOT_VOLUME: f32 = 0.3;
+/// Sounds within this many pixels of the listener play at full volume, falling off with the
+/// square of the distance beyond
+pub const FULL_VOLUME_DISTANCE: f32 = 400.;
+/// Distance between the listener's ears, in pixels. Wider pans harder.
+const EAR_GAP: f32 = 600.;
+
 #[derive(Component)]
 struct GunshotAudio;
 #[derive(Component)]
@@ -20,74 +36,73 @@ struct AircraftExplosionAudio;
 #[derive(Component)]
 struct MainMenuMusic;
 #[derive(Component)]
-struct LevelMusic;
-#[derive(Component)]
 struct WhistleAudio;
 
 /*
   Music control flow:
-    * enter MainMenu: start menu music
-    * exit MainMenu: stop menu music
-    * enter InGame: Start in-game music if not already playing
-    * enter GameOver: stop level music, if playing
+    * enter MainMenu: start menu music if not already playing
+    * enter NewGame: stop menu music
+    * enter InGame: start the level music layers if not already playing, see music.rs
+    * enter GameOver: stop level music, if playing, and play a stinger
     * enter pause: stop all sound
-    * exit pause: start all paused sound
+    * enter InGame: start all paused sound
+    * enter MainMenu: stop level music, when quitting from the pause menu
 
   Audio control flow:
     * Event listeners for explosions, gibs, gunshots
+    * Sound effects play where they happen, heard from the gun
+    * Each category of sound effect has a voice limit, see voice.rs
     * Play anytime except for pause
+    * Every sound is tagged with its mixer channel, see mixer.rs
 */
 
-fn play_menu_music(mut commands: Commands, asset_server: ResMut<AssetServer>) {
-    // Start menu music
+/// Sound effects are heard from the gun's position at the bottom of the screen
+fn spawn_listener(mut commands: Commands) {
     commands.spawn((
-        AudioBundle {
-            source: asset_server.load("audio/565_tocf_mono_intro.ogg"),
-            settings: PlaybackSettings {
-                mode: PlaybackMode::Loop,
-                ..default()
-            },
-            ..default()
-        },
-        MainMenuMusic,
+        SpatialBundle::from_transform(Transform::from_xyz(0., consts::GROUND_Y, 0.)),
+        SpatialListener::new(EAR_GAP),
     ));
 }
 
-fn stop_menu_music(
-    mut commands: Commands,
-    music_query: Query<(Entity, &AudioSink), With<MainMenuMusic>>,
-) {
-    for (entity, audio_sink) in music_query.iter() {
-        audio_sink.stop();
-        commands.entity(entity).despawn();
+/// One-shot sound panned and attenuated by where it plays relative to the listener
+fn positional(volume: Volume) -> PlaybackSettings {
+    PlaybackSettings {
+        mode: PlaybackMode::Despawn,
+        volume,
+        spatial: true,
+        spatial_scale: Some(SpatialScale::new_2d(1. / FULL_VOLUME_DISTANCE)),
+        ..default()
     }
 }
 
-fn play_level_music(
+fn play_menu_music(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
-    current_music: Query<&AudioSink, With<LevelMusic>>,
+    mixer: Res<Mixer>,
+    current_music: Query<(), With<MainMenuMusic>>,
 ) {
+    // Start menu music, which keeps playing through the menu screens
     if current_music.is_empty() {
         commands.spawn((
             AudioBundle {
-                source: asset_server.load("audio/565_tocf_mono_level_1.ogg"),
+                source: asset_server.load("audio/565_tocf_mono_intro.ogg"),
                 settings: PlaybackSettings {
                     mode: PlaybackMode::Loop,
+                    volume: mixer.volume(Channel::Music, 1.0),
                     ..default()
                 },
-                ..default()
             },
-            LevelMusic,
+            MixerChannel::new(Channel::Music, 1.0),
+            MainMenuMusic,
         ));
     }
 }
 
-fn stop_level_music(
+fn stop_menu_music(
     mut commands: Commands,
-    level_music: Query<(Entity, &AudioSink), With<LevelMusic>>,
+    music_query: Query<(Entity, &AudioSink), With<MainMenuMusic>>,
 ) {
-    for (entity, audio_sink) in level_music.iter() {
+    for (entity, audio_sink) in music_query.iter() {
         audio_sink.stop();
         commands.entity(entity).despawn();
     }
@@ -96,19 +111,18 @@ fn stop_level_music(
 fn gunshot_listener(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    mixer: Res<Mixer>,
     mut events: EventReader<GunshotEvent>,
 ) {
-    for _ in events.read() {
+    for event in events.read() {
         commands.spawn((
             AudioBundle {
                 source: asset_server.load("audio/sfx_weapon_singleshot20.wav"),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(0.3),
-                    ..default()
-                },
-                ..default()
+                settings: positional(mixer.volume(Channel::Sfx, GUNSHOT_VOLUME)),
             },
+            TransformBundle::from_transform(Transform::from_translation(event.translation)),
+            MixerChannel::new(Channel::Sfx, GUNSHOT_VOLUME),
+            Voice::new(SoundCategory::Gunshot, 1),
             GunshotAudio,
         ));
     }
@@ -118,6 +132,7 @@ fn gunshot_listener(
 fn bomb_spawned_listener(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
+    mixer: Res<Mixer>,
     bomb_query: Query<Entity, Added<Bomb>>,
 ) {
     for entity in bomb_query.iter() {
@@ -126,12 +141,10 @@ fn bomb_spawned_listener(
             (
                 AudioBundle {
                     source: asset_server.load("audio/falling-bomb-41038.ogg"),
-                    settings: PlaybackSettings {
-                        mode: PlaybackMode::Despawn,
-                        ..default()
-                    },
-                    ..default()
+                    settings: positional(mixer.volume(Channel::Sfx, 1.0)),
                 },
+                MixerChannel::new(Channel::Sfx, 1.0),
+                Voice::attached(SoundCategory::Whistle, 1),
                 WhistleAudio,
             )
         });
@@ -142,21 +155,21 @@ fn bomb_spawned_listener(
 fn bomb_explosion_listener(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
+    mixer: Res<Mixer>,
     mut events: EventReader<ExplosionEvent>,
 ) {
-    for _event in events
+    for event in events
         .read()
         .filter(|&e| e.explosion_type == ExplosionType::Bomb)
     {
         commands.spawn((
             AudioBundle {
                 source: asset_server.load("audio/bomb_explosion.wav"),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    ..default()
-                },
-                ..default()
+                settings: positional(mixer.volume(Channel::Sfx, 1.0)),
             },
+            TransformBundle::from_transform(event.transform),
+            MixerChannel::new(Channel::Sfx, 1.0),
+            Voice::new(SoundCategory::Explosion, 2),
             BombExplosionAudio,
         ));
     }
@@ -166,6 +179,8 @@ fn bomb_explosion_listener(
 fn explosion_listener(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
+    mixer: Res<Mixer>,
+    settings: Res<GameSettings>,
     mut events: EventReader<BulletCollisionEvent>,
 ) {
     for event in events.read() {
@@ -174,16 +189,15 @@ fn explosion_listener(
                 commands.spawn((
                     AudioBundle {
                         source: asset_server.load("audio/sfx_exp_double2.wav"),
-                        settings: PlaybackSettings {
-                            mode: PlaybackMode::Despawn,
-                            ..default()
-                        },
-                        ..default()
+                        settings: positional(mixer.volume(Channel::Sfx, 1.0)),
                     },
+                    TransformBundle::from_transform(Transform::from_translation(event.translation)),
+                    MixerChannel::new(Channel::Sfx, 1.0),
+                    Voice::new(SoundCategory::Explosion, 2),
                     AircraftExplosionAudio,
                 ));
             }
-            CollisionType::Paratrooper => {
+            CollisionType::Paratrooper if settings.gore => {
                 let scream_path = scream_audio_paths()
                     .choose(&mut rand::thread_rng())
                     .expect("Scream audio path not found.")
@@ -191,12 +205,11 @@ fn explosion_listener(
                 commands.spawn((
                     AudioBundle {
                         source: asset_server.load(scream_path),
-                        settings: PlaybackSettings {
-                            mode: PlaybackMode::Despawn,
-                            ..default()
-                        },
-                        ..default()
+                        settings: positional(mixer.volume(Channel::Voice, 1.0)),
                     },
+                    TransformBundle::from_transform(Transform::from_translation(event.translation)),
+                    MixerChannel::new(Channel::Voice, 1.0),
+                    Voice::new(SoundCategory::Scream, 1),
                     ScreamAudio,
                 ));
             }
@@ -209,9 +222,15 @@ fn explosion_listener(
 fn gib_listener(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
+    mixer: Res<Mixer>,
+    settings: Res<GameSettings>,
     mut events: EventReader<GibEvent>,
 ) {
-    for _event in events.read() {
+    if !settings.gore {
+        events.clear();
+        return;
+    }
+    for event in events.read() {
         let scream_path = scream_audio_paths()
             .choose(&mut rand::thread_rng())
             .expect("Scream audio path not found.")
@@ -219,12 +238,11 @@ fn gib_listener(
         commands.spawn((
             AudioBundle {
                 source: asset_server.load(scream_path),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    ..default()
-                },
-                ..default()
+                settings: positional(mixer.volume(Channel::Voice, 1.0)),
             },
+            TransformBundle::from_transform(event.transform),
+            MixerChannel::new(Channel::Voice, 1.0),
+            Voice::new(SoundCategory::Scream, 1),
             ScreamAudio,
         ));
     }
@@ -241,47 +259,71 @@ fn scream_audio_paths() -> Vec<String> {
 fn base_explosion_listener(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
+    mixer: Res<Mixer>,
     mut events: EventReader<GunExplosionEvent>,
 ) {
-    if !events.is_empty() {
-        events.clear();
+    if let Some(event) = events.read().last() {
         commands.spawn((
             AudioBundle {
                 source: asset_server.load("audio/sfx_exp_long4.wav"),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    ..default()
-                },
-                ..default()
+                settings: positional(mixer.volume(Channel::Sfx, 1.0)),
             },
+            TransformBundle::from_transform(Transform::from_translation(event.translation)),
+            MixerChannel::new(Channel::Sfx, 1.0),
+            Voice::new(SoundCategory::Explosion, 3),
             BaseExplosionAudio,
         ));
     }
 }
 
 /// Pause all active playing audio in pause (including music), resume otherwise
-fn pause_all_audio(query: Query<&AudioSink>) {
-    for audio_sink in query
+fn pause_all_audio(query: Query<&AudioSink>, spatial_query: Query<&SpatialAudioSink>) {
+    let sinks = query.iter().map(|sink| sink as &dyn AudioSinkPlayback);
+    let spatial_sinks = spatial_query
         .iter()
+        .map(|sink| sink as &dyn AudioSinkPlayback);
+    for audio_sink in sinks
+        .chain(spatial_sinks)
         .filter(|&audio_sink| !audio_sink.is_paused() && !audio_sink.empty())
     {
         audio_sink.pause();
     }
 }
 
-fn play_all_audio(query: Query<&AudioSink>) {
-    for audio_sink in query.iter().filter(|&audio_sink| audio_sink.is_paused()) {
+fn play_all_audio(query: Query<&AudioSink>, spatial_query: Query<&SpatialAudioSink>) {
+    let sinks = query.iter().map(|sink| sink as &dyn AudioSinkPlayback);
+    let spatial_sinks = spatial_query
+        .iter()
+        .map(|sink| sink as &dyn AudioSinkPlayback);
+    for audio_sink in sinks
+        .chain(spatial_sinks)
+        .filter(|&audio_sink| audio_sink.is_paused())
+    {
         audio_sink.play();
     }
 }
 
+/// Silence sound effects left paused when quitting to the menu. Whistles ride on their bombs,
+/// which go with the rest of the session.
+fn despawn_sound_effects(
+    mut commands: Commands,
+    query: Query<Entity, (With<Voice>, Without<GameSession>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
 pub struct AudioStatePlugin;
 
 impl Plugin for AudioStatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::MainMenu), play_menu_music)
-            .add_systems(OnExit(AppState::MainMenu), stop_menu_music)
-            .add_systems(OnEnter(AppState::InGame), play_level_music)
+        app.add_systems(Startup, spawn_listener)
+            .add_systems(
+                OnEnter(AppState::MainMenu),
+                (play_menu_music, despawn_sound_effects),
+            )
+            .add_systems(OnEnter(AppState::NewGame), stop_menu_music)
             .add_systems(
                 Update,
                 (
@@ -291,10 +333,10 @@ impl Plugin for AudioStatePlugin {
                     bomb_spawned_listener,
                     bomb_explosion_listener,
                     explosion_listener,
-                ),
+                )
+                    .run_if(game_time_running),
             )
             .add_systems(OnEnter(AppState::Paused), pause_all_audio)
-            .add_systems(OnExit(AppState::Paused), play_all_audio)
-            .add_systems(OnEnter(AppState::GameOver), stop_level_music);
+            .add_systems(OnEnter(AppState::InGame), play_all_audio);
     }
 }
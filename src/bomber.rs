@@ -6,13 +6,16 @@ use bevy::prelude::*;
 use bevy_rapier2d::dynamics::MassProperties;
 use bevy_rapier2d::prelude::*;
 
+use crate::animation::{Animation, AnimationLibrary, Clip};
 use crate::consts::GRAVITY;
-use crate::gun::Gun;
+use crate::gun::{Gun, GunDestroyed};
+use crate::rules::{random_spawns_enabled, Difficulty};
+use crate::session::GameSession;
 use crate::terrain::Ground;
 use rand::Rng;
 
 const BOMBER_SPAWN_PROBABILITY: f32 = 0.003;
-const BOMBER_SPEED: f32 = 300.;
+pub const BOMBER_SPEED: f32 = 300.;
 const BOMBER_SCALE: f32 = 0.3;
 const BOMB_Z: f32 = 1.9;
 const BOMB_SCALE: f32 = 0.3;
@@ -22,78 +25,96 @@ const BOMB_AIM_EPSILON: f32 = 5.0;
 const BOMB_PAYLOAD: usize = 1;
 
 #[derive(Component)]
-struct Bomber {
-    num_dropped: usize,
+pub struct Bomber {
+    pub num_dropped: usize,
 }
 
+impl Bomber {
+    pub fn has_payload(&self) -> bool {
+        self.num_dropped < BOMB_PAYLOAD
+    }
+}
+
+/// Bomber flown by a player, who releases its bombs by hand
+#[derive(Component)]
+pub struct ManualBombing;
+
 #[derive(Component)]
 pub struct Bomb;
 
 #[derive(Resource)]
-struct BomberTextures {
+pub struct BomberTextures {
     bomber_texture_handle: Handle<Image>,
-    bomb_texture_atlas_handle: Handle<TextureAtlasLayout>,
-    bomb_texture_handle: Handle<Image>,
 }
 
 /// Load textures
-fn setup_bomber_system(
-    mut commands: Commands,
-    asset_server: ResMut<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let bomb_texture_atlas = TextureAtlasLayout::from_grid(Vec2::new(64., 128.), 4, 1, None, None);
+fn setup_bomber_system(mut commands: Commands, asset_server: ResMut<AssetServer>) {
     commands.insert_resource(BomberTextures {
         bomber_texture_handle: asset_server.load("images/bomber.png"),
-        bomb_texture_atlas_handle: texture_atlases.add(bomb_texture_atlas),
-        bomb_texture_handle: asset_server.load("images/bomb4.png"),
     });
 }
 
 /// Will add toggles or whatever else with "waves"
-fn spawn_bomber_system(mut commands: Commands, textures: Res<BomberTextures>) {
+fn spawn_bomber_system(
+    mut commands: Commands,
+    textures: Res<BomberTextures>,
+    difficulty: Res<Difficulty>,
+) {
     let mut rng = rand::thread_rng();
-    if rng.gen_range(0.0..1.0) < BOMBER_SPAWN_PROBABILITY {
+    if rng.gen_range(0.0..1.0) < BOMBER_SPAWN_PROBABILITY * difficulty.spawn_rate() {
         let y = rng.gen_range(SPAWN_Y_MIN..SPAWN_Y_MAX);
         let heading_right = rng.gen_bool(0.5);
         let speed = rng.gen_range(0.8..1.3) * BOMBER_SPEED;
-        let multiplier = if heading_right { 1.0 } else { -1.0 };
-        let velocity = multiplier * speed;
-        let transform = if heading_right {
-            Transform::from_translation(Vec3::new(SPAWN_LEFT_X, y, 3.))
-        } else {
-            Transform::from_translation(Vec3::new(SPAWN_RIGHT_X, y, 3.))
-        }
-        .with_scale(Vec3::new(BOMBER_SCALE, BOMBER_SCALE, 1.));
-
-        let sprite_bundle = SpriteBundle {
-            texture: textures.bomber_texture_handle.clone(),
-            sprite: Sprite {
-                flip_x: !heading_right,
-                ..default()
-            },
-            ..default()
-        };
-
-        commands
-            .spawn(sprite_bundle)
-            .insert(transform)
-            .insert(RigidBody::Dynamic)
-            .insert(Collider::cuboid(412. / 2.0, 114. / 2.0))
-            .insert(Sensor)
-            .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC)
-            .insert(CollisionGroups::new(
-                Group::GROUP_3,
-                Group::GROUP_2 | Group::GROUP_3 | Group::GROUP_4,
-            ))
-            .insert(LockedAxes::TRANSLATION_LOCKED_Y)
-            .insert(Velocity {
-                linvel: Vec2::new(velocity, 0.),
-                angvel: 0.0,
-            })
-            .insert(Aircraft { paratroopers: 0 })
-            .insert(Bomber { num_dropped: 0 });
+        spawn_bomber(&mut commands, &textures, heading_right, y, speed);
+    }
+}
+
+/// Spawn a bomber entering from the left or right edge at altitude `y`
+pub fn spawn_bomber(
+    commands: &mut Commands,
+    textures: &BomberTextures,
+    heading_right: bool,
+    y: f32,
+    speed: f32,
+) -> Entity {
+    let multiplier = if heading_right { 1.0 } else { -1.0 };
+    let velocity = multiplier * speed;
+    let transform = if heading_right {
+        Transform::from_translation(Vec3::new(SPAWN_LEFT_X, y, 3.))
+    } else {
+        Transform::from_translation(Vec3::new(SPAWN_RIGHT_X, y, 3.))
     }
+    .with_scale(Vec3::new(BOMBER_SCALE, BOMBER_SCALE, 1.));
+
+    let sprite_bundle = SpriteBundle {
+        texture: textures.bomber_texture_handle.clone(),
+        sprite: Sprite {
+            flip_x: !heading_right,
+            ..default()
+        },
+        ..default()
+    };
+
+    commands
+        .spawn(sprite_bundle)
+        .insert(transform)
+        .insert(RigidBody::Dynamic)
+        .insert(Collider::cuboid(412. / 2.0, 114. / 2.0))
+        .insert(Sensor)
+        .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC)
+        .insert(CollisionGroups::new(
+            Group::GROUP_3,
+            Group::GROUP_2 | Group::GROUP_3 | Group::GROUP_4,
+        ))
+        .insert(LockedAxes::TRANSLATION_LOCKED_Y)
+        .insert(Velocity {
+            linvel: Vec2::new(velocity, 0.),
+            angvel: 0.0,
+        })
+        .insert(Aircraft { paratroopers: 0 })
+        .insert(Bomber { num_dropped: 0 })
+        .insert(GameSession)
+        .id()
 }
 
 /// Should drop the bomb?
@@ -101,80 +122,87 @@ fn should_bomb(bomb_transform: &Transform, velocity: &Velocity, gun_transform: &
     let drop_distance = bomb_transform.translation.y - gun_transform.translation.y;
     // Drop time without taking into account damping. Will result in short drops.
     let simple_impact_time = (-2.0 * drop_distance / GRAVITY).sqrt();
-    let pos_x = bomb_transform.translation.x - gun_transform.translation.x
+    let impact_x = bomb_transform.translation.x
         + 0.4 * velocity.linvel.x * (BOMB_DAMPING * simple_impact_time + 1.0).ln() / BOMB_DAMPING;
-    (pos_x - gun_transform.translation.x).abs() < BOMB_AIM_EPSILON
+    (impact_x - gun_transform.translation.x).abs() < BOMB_AIM_EPSILON
 }
 
 /// Set them up the bomb
 fn spawn_bombs(
     mut commands: Commands,
-    mut bomber_query: Query<(&mut Bomber, &Transform, &Velocity)>,
-    bomber_textures: Res<BomberTextures>,
-    gun_query: Query<(&Gun, &Transform)>,
+    mut bomber_query: Query<(&mut Bomber, &Transform, &Velocity), Without<ManualBombing>>,
+    animations: Res<AnimationLibrary>,
+    gun_query: Query<(&Gun, &Transform), Without<GunDestroyed>>,
     mut event_writer: EventWriter<BombDropEvent>,
 ) {
     for (_gun, gun_transform) in gun_query.iter() {
         for (mut bomber, bomber_transform, velocity) in bomber_query.iter_mut() {
-            if bomber.num_dropped < BOMB_PAYLOAD
-                && should_bomb(bomber_transform, velocity, gun_transform)
-            {
+            if bomber.has_payload() && should_bomb(bomber_transform, velocity, gun_transform) {
                 event_writer.send(BombDropEvent);
-
-                bomber.num_dropped += 1;
-                let heading = velocity.linvel.x.signum();
-                let bomb_pos = Vec2::new(
-                    bomber_transform.translation.x - heading * 35.,
-                    bomber_transform.translation.y - 25.,
+                drop_bomb(
+                    &mut commands,
+                    &animations,
+                    &mut bomber,
+                    bomber_transform,
+                    velocity,
                 );
-
-                commands
-                    .spawn(RigidBody::Dynamic)
-                    .insert(Sensor)
-                    .insert(SpriteSheetBundle {
-                        atlas: TextureAtlas {
-                            layout: bomber_textures.bomb_texture_atlas_handle.clone(),
-                            index: 0,
-                        },
-
-                        texture: bomber_textures.bomb_texture_handle.clone(),
-                        ..default()
-                    })
-                    .insert(Transform {
-                        translation: Vec3::new(bomb_pos.x, bomb_pos.y, BOMB_Z),
-                        scale: Vec3::new(BOMB_SCALE, BOMB_SCALE, 1.0),
-                        rotation: Quat::from_rotation_z(heading * std::f32::consts::FRAC_PI_2),
-                        ..Default::default()
-                    })
-                    .insert(Damping {
-                        linear_damping: BOMB_DAMPING,
-                        angular_damping: 1.0,
-                    })
-                    .insert(GravityScale(10.0))
-                    .insert(AdditionalMassProperties::MassProperties(MassProperties {
-                        mass: 10.0,
-                        principal_inertia: 0.5,
-                        ..Default::default()
-                    }))
-                    .insert(Velocity {
-                        linvel: velocity.linvel.clone(),
-                        angvel: heading * -1.5,
-                    })
-                    .insert(Collider::cuboid(
-                        BOMB_SCALE * 64.0 / 2.0,
-                        BOMB_SCALE * 128. / 2.0,
-                    ))
-                    .insert(ActiveEvents::COLLISION_EVENTS)
-                    .insert(CollisionGroups::new(
-                        Group::GROUP_3,
-                        Group::GROUP_2 | Group::GROUP_4, // bullets and gun
-                    ))
-                    .insert(Bomb);
             }
         }
     }
 }
 
+/// Release one bomb from the back of `bomber`
+pub fn drop_bomb(
+    commands: &mut Commands,
+    animations: &AnimationLibrary,
+    bomber: &mut Bomber,
+    bomber_transform: &Transform,
+    velocity: &Velocity,
+) {
+    bomber.num_dropped += 1;
+    let heading = velocity.linvel.x.signum();
+    let bomb_pos = Vec2::new(
+        bomber_transform.translation.x - heading * 35.,
+        bomber_transform.translation.y - 25.,
+    );
+
+    commands
+        .spawn(RigidBody::Dynamic)
+        .insert(Sensor)
+        .insert(animations.sprite_sheet(Clip::BombSpin, Transform::default()))
+        .insert(Animation::new(Clip::BombSpin))
+        .insert(Transform {
+            translation: Vec3::new(bomb_pos.x, bomb_pos.y, BOMB_Z),
+            scale: Vec3::new(BOMB_SCALE, BOMB_SCALE, 1.0),
+            rotation: Quat::from_rotation_z(heading * std::f32::consts::FRAC_PI_2),
+        })
+        .insert(Damping {
+            linear_damping: BOMB_DAMPING,
+            angular_damping: 1.0,
+        })
+        .insert(GravityScale(10.0))
+        .insert(AdditionalMassProperties::MassProperties(MassProperties {
+            mass: 10.0,
+            principal_inertia: 0.5,
+            ..Default::default()
+        }))
+        .insert(Velocity {
+            linvel: velocity.linvel,
+            angvel: heading * -1.5,
+        })
+        .insert(Collider::cuboid(
+            BOMB_SCALE * 64.0 / 2.0,
+            BOMB_SCALE * 128. / 2.0,
+        ))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(CollisionGroups::new(
+            Group::GROUP_3,
+            Group::GROUP_2 | Group::GROUP_4, // bullets and gun
+        ))
+        .insert(Bomb)
+        .insert(GameSession);
+}
+
 /// Bombs collisions. Gun: game over. Ground: bomb explode. Just use the same animations for now.
 fn bomb_bullet_collision_system(
     mut commands: Commands,
@@ -222,31 +250,19 @@ fn bomb_terrain_collision_system(
     }
 }
 
-fn despawn_bomber_system(
-    mut commands: Commands,
-    mut bombers: Query<Entity, Or<(With<Bomber>, With<Bomb>)>>,
-) {
-    for entity in bombers.iter_mut() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
 pub struct BomberPlugin;
 
 impl Plugin for BomberPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_bomber_system)
-            .add_systems(
-                Update,
-                (
-                    spawn_bomber_system,
-                    bomb_bullet_collision_system,
-                    bomb_terrain_collision_system,
-                    spawn_bombs,
-                )
-                    .run_if(in_state(AppState::InGame)),
+        app.add_systems(Startup, setup_bomber_system).add_systems(
+            Update,
+            (
+                spawn_bomber_system.run_if(random_spawns_enabled),
+                bomb_bullet_collision_system,
+                bomb_terrain_collision_system,
+                spawn_bombs,
             )
-            .add_systems(OnEnter(AppState::MainMenu), despawn_bomber_system)
-            .add_systems(OnEnter(AppState::GameOver), despawn_bomber_system);
+                .run_if(in_state(AppState::InGame)),
+        );
     }
 }
@@ -2,12 +2,15 @@ use crate::aircraft::Aircraft;
 use crate::bomber::Bomb;
 use crate::consts::{OUT_OF_BOUNDS_X, OUT_OF_BOUNDS_Y};
 use crate::events::*;
-use crate::gun::Gun;
+use crate::gun::{Gun, GunDestroyed};
+use crate::input::{Action, ActionInput};
 use crate::paratrooper::{Parachute, Paratrooper};
+use crate::player::{Player, PlayerControls};
+use crate::session::GameSession;
 use crate::{consts, AppState};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 #[derive(Component, Default)]
 pub struct Bullet;
@@ -25,76 +28,64 @@ fn setup_bullets(mut commands: Commands, asset_server: Res<AssetServer>) {
 
 fn shoot_gun(
     mut commands: Commands,
-    gamepads: Res<Gamepads>,
-    button_inputs: Res<ButtonInput<GamepadButton>>,
-    keyboard_inputs: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(&mut Gun, &Transform)>,
+    input: ActionInput,
+    mouse_inputs: Res<ButtonInput<MouseButton>>,
+    mut query: Query<(&mut Gun, &Transform, &Player, &PlayerControls), Without<GunDestroyed>>,
     time: Res<Time>,
     mut event_writer: EventWriter<GunshotEvent>,
     bullet_textures: Res<BulletTextures>,
 ) {
-    let keyboard_shot = keyboard_inputs.pressed(KeyCode::Space);
-    let gamepad_shot_button_types = [
-        GamepadButtonType::East,
-        GamepadButtonType::West,
-        GamepadButtonType::South,
-        GamepadButtonType::North,
-    ];
-    let mut gamepad_shot = false;
-    for gamepad in gamepads.iter() {
-        let gamepad_shot_buttons =
-            gamepad_shot_button_types.map(|button_type| GamepadButton::new(gamepad, button_type));
-        if button_inputs.any_pressed(gamepad_shot_buttons) {
-            gamepad_shot = true;
-            break;
-        }
-    }
-    if gamepad_shot || keyboard_shot {
-        for (mut gun, transform) in query.iter_mut() {
-            if time.elapsed_seconds_f64() - gun.last_fired > consts::GUN_COOLDOWN {
-                event_writer.send(GunshotEvent);
-                gun.last_fired = time.elapsed_seconds_f64();
-
-                // Spawn bullet
-                let mut bullet_transform = *transform;
-                bullet_transform.translation.z -= 0.1;
-                bullet_transform.translation += bullet_transform.local_y() * 30.;
-
-                let sprite_bundle = SpriteBundle {
-                    texture: bullet_textures.bullet_handle.clone(),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::splat(24.)),
-                        ..Default::default()
-                    },
-                    transform: bullet_transform,
+    for (mut gun, transform, &player, controls) in query.iter_mut() {
+        let mouse_shot = controls.mouse && mouse_inputs.pressed(MouseButton::Left);
+        if (mouse_shot || input.pressed(controls, Action::Fire))
+            && time.elapsed_seconds_f64() - gun.last_fired > consts::GUN_COOLDOWN
+        {
+            event_writer.send(GunshotEvent {
+                player,
+                translation: transform.translation,
+                direction: transform.local_y().truncate(),
+            });
+            gun.last_fired = time.elapsed_seconds_f64();
+
+            // Spawn bullet
+            let mut bullet_transform = *transform;
+            bullet_transform.translation.z -= 0.1;
+            bullet_transform.translation += bullet_transform.local_y() * 30.;
+
+            let sprite_bundle = SpriteBundle {
+                texture: bullet_textures.bullet_handle.clone(),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(24.)),
                     ..Default::default()
-                };
-
-                // velocity vector is local_y
-                let local_y = bullet_transform.local_y();
-                let velocity_vector = consts::BULLET_SPEED * Vec2::new(local_y.x, local_y.y);
-
-                commands
-                    .spawn(sprite_bundle)
-                    .insert(RigidBody::Dynamic)
-                    .insert(bullet_transform)
-                    .insert(Velocity {
-                        linvel: velocity_vector, //[velocity_vector.x, velocity_vector.y],
-                        angvel: 0.0,
-                    })
-                    .insert(Collider::cuboid(12., 12.))
-                    .insert(
-                        ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
-                    )
-                    .insert(ActiveEvents::COLLISION_EVENTS)
-                    .insert(CollisionGroups::new(
-                        Group::GROUP_2,
-                        Group::GROUP_1 | Group::GROUP_3 | Group::GROUP_4,
-                    ))
-                    .insert(LockedAxes::ROTATION_LOCKED)
-                    .insert(Sensor)
-                    .insert(Bullet);
-            }
+                },
+                transform: bullet_transform,
+                ..Default::default()
+            };
+
+            // velocity vector is local_y
+            let local_y = bullet_transform.local_y();
+            let velocity_vector = consts::BULLET_SPEED * Vec2::new(local_y.x, local_y.y);
+
+            commands
+                .spawn(sprite_bundle)
+                .insert(RigidBody::Dynamic)
+                .insert(bullet_transform)
+                .insert(Velocity {
+                    linvel: velocity_vector, //[velocity_vector.x, velocity_vector.y],
+                    angvel: 0.0,
+                })
+                .insert(Collider::cuboid(12., 12.))
+                .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC)
+                .insert(ActiveEvents::COLLISION_EVENTS)
+                .insert(CollisionGroups::new(
+                    Group::GROUP_2,
+                    Group::GROUP_1 | Group::GROUP_3 | Group::GROUP_4,
+                ))
+                .insert(LockedAxes::ROTATION_LOCKED)
+                .insert(Sensor)
+                .insert(Bullet)
+                .insert(player)
+                .insert(GameSession);
         }
     }
 }
@@ -103,24 +94,25 @@ fn shoot_gun(
 fn bullet_collision_system(
     mut collision_events: EventReader<CollisionEvent>,
     mut event_writer: EventWriter<BulletCollisionEvent>,
-    bullet_query: Query<(Entity, &Transform), With<Bullet>>,
+    bullet_query: Query<(Entity, &Player), With<Bullet>>,
     paratrooper_query: Query<(Entity, &Transform), With<Paratrooper>>,
     aircraft_query: Query<(Entity, &Transform), With<Aircraft>>,
     parachute_query: Query<(Entity, &Transform), With<Parachute>>,
     bomb_query: Query<&Transform, With<Bomb>>,
 ) {
-    let mut bullet_handles = HashSet::new();
-    for (bullet, _transform) in bullet_query.iter() {
-        bullet_handles.insert(bullet);
+    let mut bullet_handles = HashMap::new();
+    for (bullet, &player) in bullet_query.iter() {
+        bullet_handles.insert(bullet, player);
     }
     for collision_event in collision_events.read() {
         if let CollisionEvent::Started(entity1, entity2, _) = collision_event {
-            if bullet_handles.contains(entity1) || bullet_handles.contains(entity2) {
-                let (&bullet_entity, &target_entity) = if bullet_handles.contains(entity1) {
+            if bullet_handles.contains_key(entity1) || bullet_handles.contains_key(entity2) {
+                let (&bullet_entity, &target_entity) = if bullet_handles.contains_key(entity1) {
                     (entity1, entity2)
                 } else {
                     (entity2, entity1)
                 };
+                let player = bullet_handles[&bullet_entity];
 
                 if let Ok(bomb_transform) = bomb_query.get(target_entity) {
                     event_writer.send(BulletCollisionEvent {
@@ -128,6 +120,7 @@ fn bullet_collision_system(
                         translation: bomb_transform.translation,
                         bullet_entity,
                         target_entity,
+                        player,
                     });
                 }
 
@@ -139,6 +132,7 @@ fn bullet_collision_system(
                             translation: aircraft_transform.translation,
                             bullet_entity,
                             target_entity,
+                            player,
                         });
                     }
                 }
@@ -151,6 +145,7 @@ fn bullet_collision_system(
                             translation: parachute_transform.translation,
                             bullet_entity,
                             target_entity,
+                            player,
                         });
                     }
                 }
@@ -163,6 +158,7 @@ fn bullet_collision_system(
                             translation: paratrooper_transform.translation,
                             bullet_entity,
                             target_entity,
+                            player,
                         });
                     }
                 }
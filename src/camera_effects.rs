@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::menu::{game_time_running, AppState};
+use crate::settings::GameSettings;
+use crate::{ExplosionEvent, ExplosionType, GunExplosionEvent};
+
+/// Furthest the camera is thrown at full trauma, in pixels and radians
+const MAX_SHAKE_OFFSET: f32 = 24.;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.2;
+/// Shake frequency, in cycles per second
+const SHAKE_FREQUENCY: f32 = 14.;
+/// Frames the game stands nearly still after an aircraft is shot down, and how slowly it runs
+const HIT_STOP_FRAMES: u32 = 4;
+const HIT_STOP_SPEED: f32 = 0.05;
+
+/*
+  Shake is driven by trauma, from 0 to 1, which events add to and which decays over time. The
+  camera offset grows with the square of the trauma, so small knocks barely register while big
+  ones land hard. Both effects can be turned off in the settings.
+*/
+
+#[derive(Resource, Default)]
+struct CameraShake {
+    trauma: f32,
+    /// Runs on while shaking, to sample the shake curves
+    time: f32,
+}
+
+impl CameraShake {
+    fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.);
+    }
+}
+
+/// Frames of hit-stop left to run
+#[derive(Resource, Default)]
+struct HitStop {
+    frames: u32,
+}
+
+/// Game speed outside of hit-stop, 1 being normal. Set from the developer console.
+#[derive(Resource)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> TimeScale {
+        TimeScale(1.)
+    }
+}
+
+fn explosion_trauma(explosion_type: ExplosionType) -> f32 {
+    match explosion_type {
+        ExplosionType::Bullet => 0.15,
+        ExplosionType::Aircraft => 0.3,
+        ExplosionType::Bomb => 0.45,
+    }
+}
+
+/// Add trauma for explosions, and start a hit-stop when an aircraft goes down
+fn camera_events(
+    settings: Res<GameSettings>,
+    mut shake: ResMut<CameraShake>,
+    mut hit_stop: ResMut<HitStop>,
+    mut explosion_reader: EventReader<ExplosionEvent>,
+    mut gun_explosion_reader: EventReader<GunExplosionEvent>,
+) {
+    if !settings.screen_shake {
+        explosion_reader.clear();
+        gun_explosion_reader.clear();
+        return;
+    }
+    for event in explosion_reader.read() {
+        shake.add_trauma(explosion_trauma(event.explosion_type));
+        if event.explosion_type == ExplosionType::Aircraft {
+            hit_stop.frames = HIT_STOP_FRAMES;
+        }
+    }
+    // The finale
+    if gun_explosion_reader.read().count() > 0 {
+        shake.add_trauma(1.);
+    }
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    mut shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    if shake.trauma == 0. && shake.time == 0. {
+        return;
+    }
+    shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.);
+    shake.time += time.delta_seconds();
+    if shake.trauma == 0. {
+        shake.time = 0.;
+    }
+
+    // Sines at unrelated frequencies make a smooth shake that never quite repeats
+    let amount = shake.trauma * shake.trauma;
+    let wave = |rate: f32, phase: f32| (shake.time * SHAKE_FREQUENCY * rate + phase).sin();
+    let offset = Vec2::new(wave(1.0, 0.), wave(1.3, 1.7)) * MAX_SHAKE_OFFSET * amount;
+    let angle = wave(0.7, 4.1) * MAX_SHAKE_ANGLE * amount;
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
+
+/// Slow the game almost to a stop for a few frames, counted in frames so the pause is the same
+/// length at any frame rate
+fn run_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    time_scale: Res<TimeScale>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if hit_stop.frames > 0 {
+        hit_stop.frames -= 1;
+        time.set_relative_speed(HIT_STOP_SPEED * time_scale.0);
+    } else if time.relative_speed() != time_scale.0 {
+        time.set_relative_speed(time_scale.0);
+    }
+}
+
+/// Settle the camera and game speed when a game ends or the effects are turned off
+fn reset_camera_effects(
+    mut shake: ResMut<CameraShake>,
+    mut hit_stop: ResMut<HitStop>,
+    time_scale: Res<TimeScale>,
+    mut time: ResMut<Time<Virtual>>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    *shake = CameraShake::default();
+    *hit_stop = HitStop::default();
+    time.set_relative_speed(time_scale.0);
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x = 0.;
+        transform.translation.y = 0.;
+        transform.rotation = Quat::IDENTITY;
+    }
+}
+
+fn screen_shake_turned_off(settings: Res<GameSettings>) -> bool {
+    settings.is_changed() && !settings.screen_shake
+}
+
+pub struct CameraEffectsPlugin;
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraShake>()
+            .init_resource::<HitStop>()
+            .init_resource::<TimeScale>()
+            .add_systems(
+                Update,
+                (camera_events, shake_camera)
+                    .chain()
+                    .run_if(game_time_running),
+            )
+            .add_systems(Last, run_hit_stop)
+            .add_systems(OnEnter(AppState::MainMenu), reset_camera_effects)
+            .add_systems(OnEnter(AppState::NewGame), reset_camera_effects)
+            .add_systems(
+                PostUpdate,
+                reset_camera_effects.run_if(screen_shake_turned_off),
+            );
+    }
+}
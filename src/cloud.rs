@@ -1,20 +1,40 @@
+use crate::bullet::Bullet;
 use crate::consts::{OUT_OF_BOUNDS_X, OUT_OF_BOUNDS_Y, WINDOW_WIDTH};
+use crate::daylight::SkyTarget;
+use crate::map::Map;
+use crate::settings::GameSettings;
+use crate::weather::Weather;
+use crate::BulletCollisionEvent;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 
 const NUM_CLOUDS: usize = 7;
 const CLOUD_SPEED: f32 = 40.;
-const CLOUD_SPAWN_PROBABILITY: f32 = 0.01;
+const CLOUD_SPAWN_RATE: f32 = 0.6; // clouds / s
 const CLOUD_MIN_Y: f32 = -100.0;
 const CLOUD_MAX_Y: f32 = 400.0;
 const CLOUD_SCALE: f32 = 0.4;
 const SPAWN_LEFT_X: f32 = -WINDOW_WIDTH / 2.0 - 50.;
 const SPAWN_RIGHT_X: f32 = WINDOW_WIDTH / 2.0 + 50.;
+/// Share of a cloud texture's width and height that is solid enough to hide behind
+const CLOUD_COVER_EXTENT: f32 = 0.7;
+/// Tracers light up anything this close as they pass through a cloud
+const TRACER_REVEAL_RADIUS: f32 = 60.;
+/// Seconds a target stays in view once a tracer has found it
+const REVEAL_DURATION: f32 = 1.5;
 
 #[derive(Component)]
 pub struct Cloud;
 
+/// Seen through the clouds for a moment, after a tracer passed close by or hit it
+#[derive(Component)]
+struct Revealed(Timer);
+
+/// The part of each cloud that hides what is inside, as an ellipse: center and half extents
+#[derive(Resource, Default)]
+struct CloudCover(Vec<(Vec2, Vec2)>);
+
 #[derive(Resource)]
 struct CloudTextures {
     cloud_handles: Vec<Handle<Image>>,
@@ -29,20 +49,26 @@ fn setup_cloud_system(mut commands: Commands, asset_server: ResMut<AssetServer>)
     commands.insert_resource(CloudTextures { cloud_handles })
 }
 
-fn spawn_cloud_system(mut commands: Commands, textures: Res<CloudTextures>) {
+/// Spawns at a steady rate of game time, so none appear while paused. Each map has its own
+/// kinds of cloud, and the weather sets their pace.
+fn spawn_cloud_system(
+    mut commands: Commands,
+    textures: Res<CloudTextures>,
+    map: Res<Map>,
+    weather: Res<Weather>,
+    time: Res<Time>,
+) {
     let mut rng = rand::thread_rng();
-    if rng.gen_range(0.0..1.0) < CLOUD_SPAWN_PROBABILITY {
+    if rng.gen_range(0.0..1.0) < CLOUD_SPAWN_RATE * time.delta_seconds() {
         // Spawn random cloud
-        let cloud_idx = rng.gen_range(0..NUM_CLOUDS);
-        let cloud_handle = &textures.cloud_handles[cloud_idx];
+        let map_data = map.data();
+        let cloud_number = map_data.clouds[rng.gen_range(0..map_data.clouds.len())];
+        let cloud_handle = &textures.cloud_handles[cloud_number - 1];
 
         // Random direction
         let heading_right = rng.gen_bool(0.5);
-        let velocity = if heading_right {
-            CLOUD_SPEED
-        } else {
-            -CLOUD_SPEED
-        };
+        let speed = CLOUD_SPEED * weather.data().cloud_speed;
+        let velocity = if heading_right { speed } else { -speed };
 
         let y = rng.gen_range(CLOUD_MIN_Y..=CLOUD_MAX_Y);
         let x = if heading_right {
@@ -53,6 +79,10 @@ fn spawn_cloud_system(mut commands: Commands, textures: Res<CloudTextures>) {
 
         let sprite_bundle = SpriteBundle {
             texture: cloud_handle.clone(),
+            sprite: Sprite {
+                color: map_data.cloud_color,
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -65,13 +95,98 @@ fn spawn_cloud_system(mut commands: Commands, textures: Res<CloudTextures>) {
                 ..Default::default()
             })
             .insert(Velocity {
-                linvel: Vec2::new(velocity, 0.0).into(),
+                linvel: Vec2::new(velocity, 0.0),
                 angvel: 0.0,
             })
             .insert(Cloud);
     }
 }
 
+/// Measure the clouds for cloud cover
+fn collect_cloud_cover(
+    settings: Res<GameSettings>,
+    images: Res<Assets<Image>>,
+    cloud_query: Query<(&Transform, &Handle<Image>), With<Cloud>>,
+    mut cover: ResMut<CloudCover>,
+) {
+    cover.0.clear();
+    if !settings.cloud_cover {
+        return;
+    }
+    cover
+        .0
+        .extend(cloud_query.iter().filter_map(|(transform, handle)| {
+            let size = images.get(handle)?.size_f32();
+            let half_extents = 0.5 * CLOUD_COVER_EXTENT * size * transform.scale.truncate();
+            Some((transform.translation.truncate(), half_extents))
+        }));
+}
+
+/// With cloud cover on, aircraft, bombs and troopers inside a cloud drop out of sight unless a
+/// tracer gives them away. Bullets still fly through clouds and hit what they find there.
+fn cloud_cover_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    cover: Res<CloudCover>,
+    bullet_query: Query<&Transform, With<Bullet>>,
+    mut event_reader: EventReader<BulletCollisionEvent>,
+    mut target_query: Query<
+        (Entity, &Transform, &mut Visibility, Option<&mut Revealed>),
+        SkyTarget,
+    >,
+) {
+    if !settings.cloud_cover {
+        event_reader.clear();
+        if settings.is_changed() {
+            for (_, _, mut visibility, _) in target_query.iter_mut() {
+                *visibility = Visibility::Inherited;
+            }
+        }
+        return;
+    }
+
+    let bullets: Vec<Vec2> = bullet_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect();
+    let hit_targets: Vec<Entity> = event_reader
+        .read()
+        .map(|event| event.target_entity)
+        .collect();
+
+    for (entity, transform, mut visibility, revealed) in target_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let spotted = hit_targets.contains(&entity)
+            || bullets
+                .iter()
+                .any(|&bullet| bullet.distance(position) < TRACER_REVEAL_RADIUS);
+        let revealed = match revealed {
+            _ if spotted => {
+                commands.entity(entity).insert(Revealed(Timer::from_seconds(
+                    REVEAL_DURATION,
+                    TimerMode::Once,
+                )));
+                true
+            }
+            Some(mut revealed) => !revealed.0.tick(time.delta()).finished(),
+            None => false,
+        };
+        let covered = !revealed
+            && cover.0.iter().any(|&(center, half_extents)| {
+                ((position - center) / half_extents).length_squared() < 1.
+            });
+        let wanted = if covered {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
 fn despawn_escaped_clouds(mut commands: Commands, query: Query<(Entity, &Transform), With<Cloud>>) {
     for (entity, transform) in query.iter() {
         if transform.translation.x.abs() > OUT_OF_BOUNDS_X
@@ -88,6 +203,8 @@ impl Plugin for CloudPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_cloud_system)
             .add_systems(Update, spawn_cloud_system)
-            .add_systems(Update, despawn_escaped_clouds);
+            .add_systems(Update, despawn_escaped_clouds)
+            .init_resource::<CloudCover>()
+            .add_systems(Update, (collect_cloud_cover, cloud_cover_system).chain());
     }
 }
@@ -0,0 +1,111 @@
+use crate::player::ControlScheme;
+use crate::session::{GameSession, SessionCleanup};
+use crate::AppState;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_prototype_lyon::entity::ShapeBundle;
+use bevy_prototype_lyon::prelude::*;
+
+const CROSSHAIR_RADIUS: f32 = 10.;
+const CROSSHAIR_Z: f32 = 10.;
+const CROSSHAIR_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+/// Mouse cursor in world coordinates, if it is over the window
+#[derive(Resource, Default)]
+pub struct CursorPosition(pub Option<Vec2>);
+
+#[derive(Component)]
+struct Crosshair;
+
+fn update_cursor_position(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut cursor_position: ResMut<CursorPosition>,
+) {
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    cursor_position.0 = window
+        .cursor_position()
+        .and_then(|position| camera.viewport_to_world_2d(camera_transform, position));
+}
+
+fn spawn_crosshair(mut commands: Commands) {
+    let ring = shapes::Circle {
+        radius: CROSSHAIR_RADIUS,
+        center: Vec2::ZERO,
+    };
+    let horizontal = shapes::Line(
+        Vec2::new(-1.6 * CROSSHAIR_RADIUS, 0.),
+        Vec2::new(1.6 * CROSSHAIR_RADIUS, 0.),
+    );
+    let vertical = shapes::Line(
+        Vec2::new(0., -1.6 * CROSSHAIR_RADIUS),
+        Vec2::new(0., 1.6 * CROSSHAIR_RADIUS),
+    );
+    commands
+        .spawn(ShapeBundle {
+            path: GeometryBuilder::new()
+                .add(&ring)
+                .add(&horizontal)
+                .add(&vertical)
+                .build(),
+            ..default()
+        })
+        .insert(Stroke::new(CROSSHAIR_COLOR, 2.0))
+        .insert(Transform::from_xyz(0., 0., CROSSHAIR_Z))
+        .insert(Crosshair)
+        .insert(GameSession);
+}
+
+fn move_crosshair(
+    cursor_position: Res<CursorPosition>,
+    mut query: Query<(&mut Transform, &mut Visibility), With<Crosshair>>,
+) {
+    for (mut transform, mut visibility) in query.iter_mut() {
+        match cursor_position.0 {
+            Some(position) => {
+                transform.translation = position.extend(CROSSHAIR_Z);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// The crosshair replaces the system cursor while playing
+fn hide_system_cursor(mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in window_query.iter_mut() {
+        window.cursor.visible = false;
+    }
+}
+
+fn show_system_cursor(mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in window_query.iter_mut() {
+        window.cursor.visible = true;
+    }
+}
+
+pub struct CrosshairPlugin;
+
+impl Plugin for CrosshairPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorPosition>()
+            .init_resource::<ControlScheme>()
+            .add_systems(PreUpdate, update_cursor_position)
+            .add_systems(
+                OnEnter(AppState::NewGame),
+                spawn_crosshair
+                    .run_if(resource_equals(ControlScheme::Mouse))
+                    .after(SessionCleanup),
+            )
+            .add_systems(
+                OnEnter(AppState::InGame),
+                hide_system_cursor.run_if(resource_equals(ControlScheme::Mouse)),
+            )
+            .add_systems(OnExit(AppState::InGame), show_system_cursor)
+            .add_systems(Update, move_crosshair.run_if(in_state(AppState::InGame)));
+    }
+}
@@ -0,0 +1,299 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::entity::ShapeBundle;
+use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::aircraft::{Aircraft, Wreck};
+use crate::bomber::{Bomb, Bomber};
+use crate::bullet::Bullet;
+use crate::explosion::Explosion;
+use crate::gun::{Gun, GunDestroyed};
+use crate::paratrooper::{Parachute, Paratrooper};
+use crate::score::GameClock;
+use crate::AppState;
+
+/// Seconds of game time from one dawn to the next
+const DAY_LENGTH: f32 = 240.;
+/// How much of a target still shows in full darkness
+const NIGHT_VISIBILITY: f32 = 0.06;
+/// How dark the skyline and ground get at night
+const NIGHT_SCENERY: f32 = 0.25;
+const SEARCHLIGHT_RANGE: f32 = 1100.;
+/// Half the width of the beam, in radians
+const SEARCHLIGHT_HALF_ANGLE: f32 = 0.12;
+const SEARCHLIGHT_COLOR: Color = Color::rgb(1., 1., 0.8);
+/// Beam opacity in full darkness
+const SEARCHLIGHT_ALPHA: f32 = 0.18;
+/// How far tracers and explosions light up the sky
+const TRACER_LIGHT_RADIUS: f32 = 70.;
+const EXPLOSION_LIGHT_RADIUS: f32 = 260.;
+
+/// Sky color and daylight from 0, full night, to 1, full day, through a day starting at dawn.
+/// The last key wraps around to the first.
+const SKY_KEYS: [(f32, Color, f32); 6] = [
+    (0.0, Color::rgb(0.42, 0.62, 0.85), 1.),
+    (0.35, Color::rgb(0.42, 0.62, 0.85), 1.),
+    (0.45, Color::rgb(0.75, 0.4, 0.25), 0.5),
+    (0.55, Color::rgb(0.02, 0.02, 0.08), 0.),
+    (0.85, Color::rgb(0.02, 0.02, 0.08), 0.),
+    (0.95, Color::rgb(0.55, 0.45, 0.6), 0.5),
+];
+
+/// Time of day the game is played at, chosen on the main menu
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum DayCycle {
+    Day,
+    /// Day turns to night and back over the game
+    #[default]
+    Cycle,
+    /// Dark throughout: targets show only in the searchlight, tracers and explosions
+    Night,
+}
+
+impl DayCycle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DayCycle::Day => "Day",
+            DayCycle::Cycle => "Day & Night",
+            DayCycle::Night => "Night",
+        }
+    }
+
+    pub fn next(&self) -> DayCycle {
+        match self {
+            DayCycle::Day => DayCycle::Cycle,
+            DayCycle::Cycle => DayCycle::Night,
+            DayCycle::Night => DayCycle::Day,
+        }
+    }
+
+    /// Sky color and daylight after `seconds` of game time
+    fn sky(&self, seconds: f32) -> (Color, f32) {
+        let phase = match self {
+            DayCycle::Day => 0.,
+            DayCycle::Cycle => (seconds / DAY_LENGTH).fract(),
+            DayCycle::Night => 0.7,
+        };
+        let next = SKY_KEYS
+            .iter()
+            .position(|&(key_phase, _, _)| key_phase > phase)
+            .unwrap_or(0);
+        let (from_phase, from_color, from_light) =
+            SKY_KEYS[(next + SKY_KEYS.len() - 1) % SKY_KEYS.len()];
+        let (mut to_phase, to_color, to_light) = SKY_KEYS[next];
+        if to_phase <= from_phase {
+            to_phase += 1.;
+        }
+        let t = ((phase - from_phase) / (to_phase - from_phase)).clamp(0., 1.);
+        let (from, to) = (from_color.as_rgba_f32(), to_color.as_rgba_f32());
+        let mix = |i: usize| from[i] + (to[i] - from[i]) * t;
+        (
+            Color::rgb(mix(0), mix(1), mix(2)),
+            from_light + (to_light - from_light) * t,
+        )
+    }
+}
+
+/// How light it is, from 0 at night to 1 by day
+#[derive(Resource)]
+pub struct Daylight(pub f32);
+
+impl Default for Daylight {
+    fn default() -> Daylight {
+        Daylight(1.)
+    }
+}
+
+/// Background sprite dimmed as night falls, drawn in `color` by day
+#[derive(Component)]
+pub struct Scenery {
+    pub color: Color,
+}
+
+/// Target sprite shaded by the light, drawn in `color` when fully lit. Kept so tints such as
+/// a wreck's survive the night.
+#[derive(Component)]
+struct LitColor {
+    color: Color,
+}
+
+/// Aircraft, bombs and troopers: what the guns shoot at
+pub type SkyTarget = Or<(With<Aircraft>, With<Bomber>, With<Bomb>, With<Paratrooper>)>;
+/// Targets darkened at night, chutes and wrecks included
+type LitTarget = Or<(SkyTarget, With<Parachute>, With<Wreck>)>;
+/// Tracers and explosions light up what is near them
+type LightSource = Or<(With<Bullet>, With<Explosion>)>;
+
+/// Beam from a gun's barrel, turning with it
+#[derive(Component)]
+struct Searchlight;
+
+/// Mount a searchlight on each new gun barrel. It only shows after dark.
+fn attach_searchlights(mut commands: Commands, gun_query: Query<Entity, Added<Gun>>) {
+    let reach = SEARCHLIGHT_HALF_ANGLE.tan() * SEARCHLIGHT_RANGE;
+    let beam = shapes::Polygon {
+        points: vec![
+            Vec2::ZERO,
+            Vec2::new(-reach, SEARCHLIGHT_RANGE),
+            Vec2::new(reach, SEARCHLIGHT_RANGE),
+        ],
+        closed: true,
+    };
+    for entity in gun_query.iter() {
+        let searchlight = commands
+            .spawn(ShapeBundle {
+                path: GeometryBuilder::build_as(&beam),
+                ..default()
+            })
+            .insert(Fill::color(SEARCHLIGHT_COLOR.with_a(0.)))
+            // In front of the planes and troopers, behind the clouds
+            .insert(Transform::from_xyz(0., 0., 5.))
+            .insert(Visibility::Hidden)
+            .insert(Searchlight)
+            .id();
+        commands.entity(entity).add_child(searchlight);
+    }
+}
+
+/// Remember the color each new target was spawned in
+fn store_lit_colors(
+    mut commands: Commands,
+    target_query: Query<(Entity, &Sprite), (LitTarget, Without<LitColor>)>,
+) {
+    for (entity, sprite) in target_query.iter() {
+        commands.entity(entity).insert(LitColor {
+            color: sprite.color,
+        });
+    }
+}
+
+/// Follow the game clock through the day, coloring the sky and dimming the scenery
+fn update_daylight(
+    game_clock: Res<GameClock>,
+    day_cycle: Res<DayCycle>,
+    mut daylight: ResMut<Daylight>,
+    mut clear_color: ResMut<ClearColor>,
+    mut scenery_query: Query<(&Scenery, &mut Sprite)>,
+) {
+    let (sky, light) = day_cycle.sky(game_clock.duration().as_secs_f32());
+    if (daylight.0 - light).abs() < 0.001 && clear_color.0 == sky {
+        return;
+    }
+    daylight.0 = light;
+    clear_color.0 = sky;
+    let shade = NIGHT_SCENERY + (1. - NIGHT_SCENERY) * light;
+    for (scenery, mut sprite) in scenery_query.iter_mut() {
+        sprite.color = shade_color(scenery.color, shade);
+    }
+}
+
+fn shade_color(color: Color, shade: f32) -> Color {
+    let [r, g, b, a] = color.as_rgba_f32();
+    Color::rgba(r * shade, g * shade, b * shade, a)
+}
+
+/// Show the beams after dark, and only from guns still standing
+fn update_searchlights(
+    daylight: Res<Daylight>,
+    gun_query: Query<Has<GunDestroyed>, With<Gun>>,
+    mut searchlight_query: Query<(&Parent, &mut Visibility, &mut Fill), With<Searchlight>>,
+) {
+    let strength = 1. - daylight.0;
+    for (parent, mut visibility, mut fill) in searchlight_query.iter_mut() {
+        let destroyed = gun_query.get(parent.get()).unwrap_or(true);
+        let on = strength > 0. && !destroyed;
+        *visibility = if on {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        fill.color = SEARCHLIGHT_COLOR.with_a(SEARCHLIGHT_ALPHA * strength);
+    }
+}
+
+/// Darken aircraft, bombs, troopers and wrecks unless something lights them up: a searchlight
+/// beam, a passing tracer or a nearby explosion
+fn light_targets(
+    daylight: Res<Daylight>,
+    searchlight_query: Query<(&GlobalTransform, &InheritedVisibility), With<Searchlight>>,
+    light_query: Query<(&GlobalTransform, Has<Explosion>), LightSource>,
+    mut target_query: Query<(&GlobalTransform, &LitColor, &mut Sprite), LitTarget>,
+) {
+    let beams: Vec<(Vec2, Vec2)> = searchlight_query
+        .iter()
+        .filter(|(_, visibility)| visibility.get())
+        .map(|(transform, _)| {
+            let (_, rotation, translation) = transform.to_scale_rotation_translation();
+            (translation.truncate(), (rotation * Vec3::Y).truncate())
+        })
+        .collect();
+    let lights: Vec<(Vec2, f32)> = light_query
+        .iter()
+        .map(|(transform, explosion)| {
+            let radius = if explosion {
+                EXPLOSION_LIGHT_RADIUS
+            } else {
+                TRACER_LIGHT_RADIUS
+            };
+            (transform.translation().truncate(), radius)
+        })
+        .collect();
+
+    for (transform, lit_color, mut sprite) in target_query.iter_mut() {
+        let position = transform.translation().truncate();
+        let in_beam = beams.iter().any(|&(origin, direction)| {
+            let offset = position - origin;
+            offset.length() < SEARCHLIGHT_RANGE
+                && offset.angle_between(direction).abs() < SEARCHLIGHT_HALF_ANGLE
+        });
+        let lit = if in_beam {
+            1.
+        } else {
+            lights
+                .iter()
+                .map(|&(light, radius)| 1. - (position.distance(light) / radius).min(1.))
+                .fold(0., f32::max)
+        };
+        let darkness = 1. - daylight.0;
+        let visibility = 1. - darkness * (1. - lit.max(NIGHT_VISIBILITY));
+        let color = shade_color(lit_color.color, visibility);
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Back to the plain menu backdrop
+fn reset_sky(
+    mut daylight: ResMut<Daylight>,
+    mut clear_color: ResMut<ClearColor>,
+    mut scenery_query: Query<(&Scenery, &mut Sprite)>,
+) {
+    *daylight = Daylight::default();
+    clear_color.0 = Color::BLACK;
+    for (scenery, mut sprite) in scenery_query.iter_mut() {
+        sprite.color = scenery.color;
+    }
+}
+
+pub struct DaylightPlugin;
+
+impl Plugin for DaylightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DayCycle>()
+            .init_resource::<Daylight>()
+            .add_systems(OnEnter(AppState::MainMenu), reset_sky)
+            .add_systems(
+                Update,
+                (
+                    attach_searchlights,
+                    store_lit_colors,
+                    update_daylight,
+                    update_searchlights,
+                    light_targets,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
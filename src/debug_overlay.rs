@@ -0,0 +1,180 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_rapier2d::render::{DebugRenderContext, RapierDebugRenderPlugin};
+
+use crate::aircraft::Aircraft;
+use crate::bomber::Bomb;
+use crate::bullet::Bullet;
+use crate::cloud::Cloud;
+use crate::menu::FontHandles;
+use crate::paratrooper::Paratrooper;
+
+const DEBUG_TOGGLE_KEY: KeyCode = KeyCode::F3;
+const DEBUG_FONT_SIZE: f32 = 18.;
+const DEBUG_COLOR: Color = Color::YELLOW;
+/// Trooper labels sit above the head, in the trooper's scaled space
+const LABEL_OFFSET: Vec3 = Vec3::new(0., 70., 1.);
+
+/*
+  Only built with `--features debug_overlay`. F3 shows the Rapier collider outlines, frame
+  rate, entity counts and each trooper's state, for tuning collisions and spawn rates.
+*/
+
+#[derive(Resource, Default)]
+struct DebugOverlay {
+    visible: bool,
+}
+
+#[derive(Component)]
+struct DebugText;
+
+/// Shows the state of the trooper it is attached to
+#[derive(Component)]
+struct TrooperLabel;
+
+fn spawn_debug_text(mut commands: Commands, fonts: Res<FontHandles>) {
+    commands
+        .spawn(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: fonts.handle.clone(),
+                    font_size: DEBUG_FONT_SIZE,
+                    color: DEBUG_COLOR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(60.),
+                ..default()
+            }),
+        )
+        .insert(Visibility::Hidden)
+        .insert(DebugText);
+}
+
+fn toggle_debug_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut debug_render: ResMut<DebugRenderContext>,
+    mut text_query: Query<&mut Visibility, With<DebugText>>,
+    mut label_query: Query<&mut Visibility, (With<TrooperLabel>, Without<DebugText>)>,
+) {
+    if !keyboard_input.just_pressed(DEBUG_TOGGLE_KEY) {
+        return;
+    }
+    overlay.visible = !overlay.visible;
+    debug_render.enabled = overlay.visible;
+    let visibility = if overlay.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut text_visibility in text_query.iter_mut().chain(label_query.iter_mut()) {
+        *text_visibility = visibility;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_debug_text(
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    entity_query: Query<Entity>,
+    bullet_query: Query<(), With<Bullet>>,
+    paratrooper_query: Query<(), With<Paratrooper>>,
+    aircraft_query: Query<(), With<Aircraft>>,
+    bomb_query: Query<(), With<Bomb>>,
+    cloud_query: Query<(), With<Cloud>>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    if !overlay.visible {
+        return;
+    }
+    let diagnostic = |path| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or(0.)
+    };
+    let lines = [
+        format!(
+            "FPS: {:.0} ({:.1} ms)",
+            diagnostic(&FrameTimeDiagnosticsPlugin::FPS),
+            diagnostic(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        ),
+        format!("Entities: {}", entity_query.iter().count()),
+        format!("Bullets: {}", bullet_query.iter().count()),
+        format!("Paratroopers: {}", paratrooper_query.iter().count()),
+        format!("Aircraft: {}", aircraft_query.iter().count()),
+        format!("Bombs: {}", bomb_query.iter().count()),
+        format!("Clouds: {}", cloud_query.iter().count()),
+    ];
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+/// Label each new trooper, and relabel it as its state changes
+fn label_troopers(
+    mut commands: Commands,
+    fonts: Res<FontHandles>,
+    overlay: Res<DebugOverlay>,
+    paratrooper_query: Query<(Entity, &Paratrooper, Option<&Children>), Changed<Paratrooper>>,
+    mut label_query: Query<&mut Text, With<TrooperLabel>>,
+) {
+    for (entity, paratrooper, children) in paratrooper_query.iter() {
+        let state = format!("{:?}", paratrooper.state);
+        let existing = children.and_then(|children| {
+            children
+                .iter()
+                .copied()
+                .find(|&child| label_query.contains(child))
+        });
+        if let Some(label) = existing {
+            if let Ok(mut text) = label_query.get_mut(label) {
+                text.sections[0].value = state;
+            }
+            continue;
+        }
+        let label = commands
+            .spawn(Text2dBundle {
+                text: Text::from_section(
+                    state,
+                    TextStyle {
+                        font: fonts.handle.clone(),
+                        font_size: 2. * DEBUG_FONT_SIZE,
+                        color: DEBUG_COLOR,
+                    },
+                ),
+                transform: Transform::from_translation(LABEL_OFFSET),
+                visibility: if overlay.visible {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..default()
+            })
+            .insert(TrooperLabel)
+            .id();
+        commands.entity(entity).add_child(label);
+    }
+}
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin)
+            .add_plugins(RapierDebugRenderPlugin {
+                enabled: false,
+                ..default()
+            })
+            .init_resource::<DebugOverlay>()
+            .add_systems(PostStartup, spawn_debug_text)
+            .add_systems(
+                Update,
+                (toggle_debug_overlay, update_debug_text, label_troopers).chain(),
+            );
+    }
+}
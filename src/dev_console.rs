@@ -0,0 +1,347 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+use rand::Rng;
+use std::time::Duration;
+
+use crate::aircraft::{
+    spawn_aircraft, AircraftTextures, StickSize, AIRCRAFT_SPEED, SPAWN_Y_MAX, SPAWN_Y_MIN,
+};
+use crate::bomber::{spawn_bomber, BomberTextures, BOMBER_SPEED};
+use crate::camera_effects::TimeScale;
+use crate::gun::GodMode;
+use crate::menu::FontHandles;
+use crate::rules::WAVE_DURATION;
+use crate::score::{GameClock, Score};
+use crate::AppState;
+
+const CONSOLE_TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+const CONSOLE_FONT_SIZE: f32 = 20.;
+const CONSOLE_COLOR: Color = Color::rgb(0.6, 1., 0.6);
+const CONSOLE_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.8);
+/// Lines of history kept on screen above the prompt
+const HISTORY_LINES: usize = 10;
+const HELP: &str = "Commands: spawn aircraft|bomber left|right [altitude], stick <size>, god, \
+                    wave <number>, timescale <speed>, score reset";
+
+/*
+  Only built with `--features dev_console`. The backquote key drops down a command line for
+  setting up situations by hand while testing. Keyboard input goes to the console while it is
+  open, so typing doesn't fire the gun.
+*/
+
+#[derive(Resource, Default)]
+struct DevConsole {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+}
+
+impl DevConsole {
+    fn print(&mut self, line: impl Into<String>) {
+        self.history.push(line.into());
+        if self.history.len() > HISTORY_LINES {
+            self.history.remove(0);
+        }
+    }
+}
+
+/// A parsed console command, waiting to be run
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+enum ConsoleCommand {
+    Help,
+    /// Altitude is random when not given
+    SpawnAircraft {
+        heading_right: bool,
+        altitude: Option<f32>,
+    },
+    SpawnBomber {
+        heading_right: bool,
+        altitude: Option<f32>,
+    },
+    StickSize(usize),
+    /// Toggle gun invulnerability
+    God,
+    Wave(u32),
+    TimeScale(f32),
+    ResetScore,
+}
+
+impl ConsoleCommand {
+    /// Commands that only make sense with a game going
+    fn needs_game(&self) -> bool {
+        matches!(
+            self,
+            ConsoleCommand::SpawnAircraft { .. }
+                | ConsoleCommand::SpawnBomber { .. }
+                | ConsoleCommand::Wave(_)
+                | ConsoleCommand::ResetScore
+        )
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(word: Option<&str>, what: &str) -> Result<T, String> {
+    let word = word.ok_or_else(|| format!("Missing {}", what))?;
+    word.parse().map_err(|_| format!("Bad {}: {}", what, word))
+}
+
+fn parse_command(line: &str) -> Result<ConsoleCommand, String> {
+    let mut words = line.split_whitespace();
+    let command = match words.next() {
+        Some("help") => ConsoleCommand::Help,
+        Some("spawn") => {
+            let kind = words.next();
+            // The side the plane enters from
+            let heading_right = match words.next() {
+                Some("left") => true,
+                Some("right") => false,
+                _ => return Err("Usage: spawn aircraft|bomber left|right [altitude]".into()),
+            };
+            let altitude = match words.next() {
+                Some(word) => Some(parse_number(Some(word), "altitude")?),
+                None => None,
+            };
+            match kind {
+                Some("aircraft") => ConsoleCommand::SpawnAircraft {
+                    heading_right,
+                    altitude,
+                },
+                Some("bomber") => ConsoleCommand::SpawnBomber {
+                    heading_right,
+                    altitude,
+                },
+                _ => return Err("Usage: spawn aircraft|bomber left|right [altitude]".into()),
+            }
+        }
+        Some("stick") => ConsoleCommand::StickSize(parse_number(words.next(), "stick size")?),
+        Some("god") => ConsoleCommand::God,
+        Some("wave") => {
+            let wave = parse_number(words.next(), "wave")?;
+            if wave == 0 {
+                return Err("Waves start at 1".into());
+            }
+            ConsoleCommand::Wave(wave)
+        }
+        Some("timescale") => {
+            let speed: f32 = parse_number(words.next(), "time scale")?;
+            if !speed.is_finite() || speed <= 0. {
+                return Err("Time scale must be above 0".into());
+            }
+            ConsoleCommand::TimeScale(speed)
+        }
+        Some("score") if words.next() == Some("reset") => ConsoleCommand::ResetScore,
+        Some(word) => return Err(format!("Unknown command: {}. Try help", word)),
+        None => return Err("Try help".into()),
+    };
+    match words.next() {
+        Some(extra) => Err(format!("Unexpected: {}", extra)),
+        None => Ok(command),
+    }
+}
+
+#[derive(Component)]
+struct ConsolePanel;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands, fonts: Res<FontHandles>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.),
+                width: Val::Percent(100.),
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            },
+            background_color: CONSOLE_BACKGROUND.into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(100),
+            ..default()
+        })
+        .insert(ConsolePanel)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.handle.clone(),
+                        font_size: CONSOLE_FONT_SIZE,
+                        color: CONSOLE_COLOR,
+                    },
+                ))
+                .insert(ConsoleText);
+        });
+}
+
+/// Open and close the console, and take the keyboard while it is open. Runs straight after
+/// Bevy reads the keyboard, so the game never sees the keys.
+fn console_input(
+    mut console: ResMut<DevConsole>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut char_reader: EventReader<ReceivedCharacter>,
+    mut command_writer: EventWriter<ConsoleCommand>,
+) {
+    if keyboard_input.just_pressed(CONSOLE_TOGGLE_KEY) {
+        console.open = !console.open;
+        char_reader.clear();
+        keyboard_input.reset_all();
+        return;
+    }
+    if !console.open {
+        char_reader.clear();
+        return;
+    }
+
+    for event in char_reader.read() {
+        let typed = event.char.chars().filter(|c| !c.is_control() && *c != '`');
+        console.input.extend(typed);
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        console.open = false;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        let line = std::mem::take(&mut console.input);
+        console.print(format!("> {}", line));
+        match parse_command(&line) {
+            Ok(command) => {
+                command_writer.send(command);
+            }
+            Err(message) => console.print(message),
+        }
+    }
+    keyboard_input.reset_all();
+}
+
+/// Game state the console commands change
+#[derive(SystemParam)]
+struct Cheats<'w, 's> {
+    aircraft_textures: Res<'w, AircraftTextures>,
+    bomber_textures: Res<'w, BomberTextures>,
+    stick_size: ResMut<'w, StickSize>,
+    god_mode: ResMut<'w, GodMode>,
+    time_scale: ResMut<'w, TimeScale>,
+    /// Only there once a game has started
+    game_clock: Option<ResMut<'w, GameClock>>,
+    score: ResMut<'w, Score>,
+    gun_score_query: Query<'w, 's, &'static mut Score>,
+}
+
+fn run_console_commands(
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    mut console: ResMut<DevConsole>,
+    mut command_reader: EventReader<ConsoleCommand>,
+    mut cheats: Cheats,
+) {
+    let in_game = matches!(state.get(), AppState::InGame | AppState::Paused);
+    let mut rng = rand::thread_rng();
+    for &command in command_reader.read() {
+        if command.needs_game() && !in_game {
+            console.print("Start a game first");
+            continue;
+        }
+        match command {
+            ConsoleCommand::Help => console.print(HELP),
+            ConsoleCommand::SpawnAircraft {
+                heading_right,
+                altitude,
+            } => {
+                let y = altitude.unwrap_or_else(|| rng.gen_range(SPAWN_Y_MIN..SPAWN_Y_MAX));
+                spawn_aircraft(
+                    &mut commands,
+                    &cheats.aircraft_textures,
+                    heading_right,
+                    y,
+                    AIRCRAFT_SPEED,
+                    cheats.stick_size.0,
+                );
+                console.print(format!("Aircraft at {:.0}", y));
+            }
+            ConsoleCommand::SpawnBomber {
+                heading_right,
+                altitude,
+            } => {
+                let y = altitude.unwrap_or_else(|| rng.gen_range(SPAWN_Y_MIN..SPAWN_Y_MAX));
+                spawn_bomber(
+                    &mut commands,
+                    &cheats.bomber_textures,
+                    heading_right,
+                    y,
+                    BOMBER_SPEED,
+                );
+                console.print(format!("Bomber at {:.0}", y));
+            }
+            ConsoleCommand::StickSize(size) => {
+                cheats.stick_size.0 = size;
+                console.print(format!("Stick size {}", size));
+            }
+            ConsoleCommand::God => {
+                cheats.god_mode.0 = !cheats.god_mode.0;
+                console.print(if cheats.god_mode.0 {
+                    "God mode on"
+                } else {
+                    "God mode off"
+                });
+            }
+            ConsoleCommand::Wave(wave) => {
+                // The wave follows from the clock, so jump to the start of the wave
+                if let Some(game_clock) = cheats.game_clock.as_mut() {
+                    let seconds = (wave - 1) as f32 * WAVE_DURATION;
+                    game_clock.set_duration(Duration::from_secs_f32(seconds));
+                    console.print(format!("Wave {}", wave));
+                }
+            }
+            ConsoleCommand::TimeScale(speed) => {
+                cheats.time_scale.0 = speed;
+                console.print(format!("Time scale {}", speed));
+            }
+            ConsoleCommand::ResetScore => {
+                *cheats.score = Score::default();
+                for mut gun_score in cheats.gun_score_query.iter_mut() {
+                    *gun_score = Score::default();
+                }
+                console.print("Score reset");
+            }
+        }
+    }
+}
+
+fn update_console_ui(
+    console: Res<DevConsole>,
+    mut panel_query: Query<&mut Visibility, With<ConsolePanel>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut visibility in panel_query.iter_mut() {
+        *visibility = if console.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    let mut lines = console.history.clone();
+    lines.push(format!("> {}_", console.input));
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+pub struct DevConsolePlugin;
+
+impl Plugin for DevConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DevConsole>()
+            .add_event::<ConsoleCommand>()
+            .add_systems(PostStartup, spawn_console)
+            .add_systems(PreUpdate, console_input.after(InputSystem))
+            .add_systems(Update, (run_console_commands, update_console_ui).chain());
+    }
+}
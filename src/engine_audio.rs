@@ -0,0 +1,209 @@
+use bevy::audio::{
+    AddAudioSource, AudioSinkPlayback, Decodable, PlaybackMode, Source, SpatialAudioSink,
+    SpatialListener, SpatialScale,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use crate::aircraft::Aircraft;
+use crate::audio::FULL_VOLUME_DISTANCE;
+use crate::bomber::Bomber;
+use crate::menu::game_time_running;
+use crate::mixer::{Channel, Mixer, MixerChannel};
+
+const SAMPLE_RATE: u32 = 44_100;
+const ENGINE_VOLUME: f32 = 0.25;
+/// Exaggerated speed of sound in pixels per second, so the pitch shift is audible at the
+/// speeds planes fly
+const SOUND_SPEED: f32 = 900.;
+const MIN_PITCH: f32 = 0.5;
+const MAX_PITCH: f32 = 2.0;
+
+/// Endless engine drone, synthesized rather than loaded: a buzzing tone for the fundamental,
+/// pulsed by the propeller, over a little noise
+#[derive(Asset, TypePath, Clone, Copy)]
+pub struct EngineSound {
+    /// Fundamental in Hz
+    frequency: f32,
+    /// Propeller pulse rate in Hz
+    beat: f32,
+    /// Second engine's offset from the fundamental in Hz, zero for single engine planes
+    detune: f32,
+    /// Share of noise in the mix
+    roughness: f32,
+}
+
+pub struct EngineDecoder {
+    sound: EngineSound,
+    phases: [f32; 2],
+    beat_phase: f32,
+    noise_seed: u32,
+    noise: f32,
+}
+
+impl Decodable for EngineSound {
+    type DecoderItem = f32;
+    type Decoder = EngineDecoder;
+
+    fn decoder(&self) -> EngineDecoder {
+        EngineDecoder {
+            sound: *self,
+            phases: [0., 0.25],
+            beat_phase: 0.,
+            noise_seed: 0x2545_f491,
+            noise: 0.,
+        }
+    }
+}
+
+impl EngineDecoder {
+    /// Cheap xorshift white noise in -1..1
+    fn white_noise(&mut self) -> f32 {
+        self.noise_seed ^= self.noise_seed << 13;
+        self.noise_seed ^= self.noise_seed >> 17;
+        self.noise_seed ^= self.noise_seed << 5;
+        self.noise_seed as f32 / u32::MAX as f32 * 2. - 1.
+    }
+}
+
+impl Iterator for EngineDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let dt = 1. / SAMPLE_RATE as f32;
+        let frequencies = [
+            self.sound.frequency,
+            self.sound.frequency + self.sound.detune,
+        ];
+        let engines = if self.sound.detune == 0. { 1 } else { 2 };
+        let mut tone = 0.;
+        for (phase, frequency) in self.phases.iter_mut().zip(frequencies).take(engines) {
+            *phase = (*phase + frequency * dt).fract();
+            // Sawtooth, for the buzz of a piston engine
+            tone += (2. * *phase - 1.) / engines as f32;
+        }
+        self.beat_phase = (self.beat_phase + self.sound.beat * dt).fract();
+        let pulse = 0.75 + 0.25 * (TAU * self.beat_phase).sin();
+
+        // Low-passed noise rumbles rather than hisses
+        let white_noise = self.white_noise();
+        self.noise += 0.05 * (white_noise - self.noise);
+
+        let roughness = self.sound.roughness;
+        Some(pulse * ((1. - roughness) * tone + roughness * 4. * self.noise))
+    }
+}
+
+impl Source for EngineDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Resource)]
+struct EngineSounds {
+    aircraft: Handle<EngineSound>,
+    bomber: Handle<EngineSound>,
+}
+
+/// Engine drone playing on a plane
+#[derive(Component)]
+struct EngineAudio;
+
+fn setup_engine_sounds(mut commands: Commands, mut assets: ResMut<Assets<EngineSound>>) {
+    // A light, fast-revving transport against a deep, heavy bomber with two droning engines
+    commands.insert_resource(EngineSounds {
+        aircraft: assets.add(EngineSound {
+            frequency: 140.,
+            beat: 24.,
+            detune: 0.,
+            roughness: 0.3,
+        }),
+        bomber: assets.add(EngineSound {
+            frequency: 55.,
+            beat: 9.,
+            detune: 1.5,
+            roughness: 0.45,
+        }),
+    });
+}
+
+/// Attach an engine to each new plane. The sound plays from the plane, so it pans as the plane
+/// flies, and stops when the plane is despawned.
+fn start_engines(
+    mut commands: Commands,
+    engine_sounds: Res<EngineSounds>,
+    mixer: Res<Mixer>,
+    aircraft_query: Query<Entity, Added<Aircraft>>,
+    bomber_query: Query<Entity, Added<Bomber>>,
+) {
+    let engines = aircraft_query
+        .iter()
+        .map(|entity| (entity, &engine_sounds.aircraft))
+        .chain(
+            bomber_query
+                .iter()
+                .map(|entity| (entity, &engine_sounds.bomber)),
+        );
+    for (entity, sound) in engines {
+        commands.entity(entity).insert((
+            AudioSourceBundle {
+                source: sound.clone(),
+                settings: PlaybackSettings {
+                    // The drone never ends on its own
+                    mode: PlaybackMode::Once,
+                    volume: mixer.volume(Channel::Sfx, ENGINE_VOLUME),
+                    spatial: true,
+                    spatial_scale: Some(SpatialScale::new_2d(1. / FULL_VOLUME_DISTANCE)),
+                    ..default()
+                },
+            },
+            MixerChannel::new(Channel::Sfx, ENGINE_VOLUME),
+            EngineAudio,
+        ));
+    }
+}
+
+/// Raise the pitch of planes closing on the listener and lower it for planes flying away
+fn doppler_shift(
+    listener_query: Query<&GlobalTransform, With<SpatialListener>>,
+    engine_query: Query<(&GlobalTransform, &Velocity, &SpatialAudioSink), With<EngineAudio>>,
+) {
+    let Ok(listener) = listener_query.get_single() else {
+        return;
+    };
+    let listener = listener.translation().truncate();
+    for (transform, velocity, audio_sink) in engine_query.iter() {
+        let direction = (transform.translation().truncate() - listener).normalize_or_zero();
+        let receding_speed = velocity.linvel.dot(direction);
+        let pitch = (SOUND_SPEED / (SOUND_SPEED + receding_speed)).clamp(MIN_PITCH, MAX_PITCH);
+        audio_sink.set_speed(pitch);
+    }
+}
+
+pub struct EngineAudioPlugin;
+
+impl Plugin for EngineAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<EngineSound>()
+            .add_systems(Startup, setup_engine_sounds)
+            .add_systems(
+                Update,
+                (start_engines, doppler_shift).run_if(game_time_running),
+            );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::player::Player;
 use bevy::prelude::*;
 
 #[derive(Event)]
@@ -6,11 +7,15 @@ pub struct BulletCollisionEvent {
     pub collision_type: CollisionType,
     pub bullet_entity: Entity,
     pub target_entity: Entity,
+    /// Who fired the bullet
+    pub player: Player,
 }
 
 #[derive(Event)]
 pub struct GunExplosionEvent {
     pub translation: Vec3,
+    /// Whose gun was destroyed
+    pub player: Player,
 }
 
 #[derive(PartialEq, Event)]
@@ -22,7 +27,13 @@ pub enum CollisionType {
 }
 
 #[derive(Event)]
-pub struct GunshotEvent;
+pub struct GunshotEvent {
+    pub player: Player,
+    /// Where the gun fired from
+    pub translation: Vec3,
+    /// Unit vector along the barrel
+    pub direction: Vec2,
+}
 
 #[derive(Event)]
 pub struct LandingEvent(pub Entity);
@@ -30,6 +41,12 @@ pub struct LandingEvent(pub Entity);
 #[derive(Event)]
 pub struct BombDropEvent;
 
+/// An aircraft left the screen, with any paratroopers still on board.
+#[derive(Event)]
+pub struct AircraftEscapeEvent {
+    pub paratroopers: usize,
+}
+
 /// Animation events.
 #[derive(Event)]
 pub struct ExplosionEvent {
@@ -47,18 +64,31 @@ pub enum ExplosionType {
 #[derive(Event)]
 pub struct GibEvent {
     pub transform: Transform,
+    /// Who gets credit for the kill, if anyone
+    pub player: Option<Player>,
 }
 
+/// A new wave of the attack began. Waves are numbered from 1.
+#[derive(Event)]
+pub struct WaveStartEvent(pub u32);
+
+/// A menu button was activated with Confirm or a click
+#[derive(Event)]
+pub struct MenuButtonPressed(pub Entity);
+
 pub struct EventPlugin;
 
 impl Plugin for EventPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ExplosionEvent>()
             .add_event::<BombDropEvent>()
+            .add_event::<AircraftEscapeEvent>()
             .add_event::<GibEvent>()
             .add_event::<GunExplosionEvent>()
             .add_event::<BulletCollisionEvent>()
             .add_event::<GunshotEvent>()
-            .add_event::<LandingEvent>();
+            .add_event::<LandingEvent>()
+            .add_event::<WaveStartEvent>()
+            .add_event::<MenuButtonPressed>();
     }
 }
@@ -6,11 +6,22 @@ use bevy_rapier2d::prelude::*;
 use std::collections::HashSet;
 
 use crate::bomber::Bomb;
+use crate::crosshair::CursorPosition;
+use crate::input::ActionInput;
 use crate::paratrooper::Paratrooper;
+use crate::player::{ControlScheme, Player, PlayerControls};
+use crate::rules::GameMode;
+use crate::score::Score;
+use crate::session::{GameSession, SessionCleanup};
 use crate::{consts, AppState, GunExplosionEvent};
 
 const ANGULAR_VELOCITY: f32 = 2.5;
 
+/// Fastest the barrel turns toward the mouse cursor
+const MOUSE_ANGULAR_VELOCITY: f32 = 4.0;
+/// Turn rate per radian of aiming error when following the mouse
+const MOUSE_AIM_GAIN: f32 = 12.0;
+
 /// Right-side angle boundary
 const BOUNDARY_ANGLE: f32 = std::f32::consts::PI / 2.9;
 
@@ -34,7 +45,63 @@ const GUN_MOUNT_Y: f32 = 18.;
 const GUN_HEIGHT: f32 = 35.;
 const GUN_WIDTH: f32 = 10.;
 
-pub fn setup_gun_base(mut commands: Commands) {
+/// Horizontal distance of each gun from the center in two-player modes
+const GUN_SPACING_X: f32 = consts::WINDOW_WIDTH / 4.;
+
+const DESTROYED_GUN_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+
+/// Marks every part of a gun assembly that has been blown up
+#[derive(Component)]
+pub struct GunDestroyed;
+
+/// Guns shrug off bombs and landed paratroopers. Set from the developer console.
+#[derive(Resource, Default)]
+pub struct GodMode(pub bool);
+
+/// Spawn one gun assembly per player. Player one may aim with the mouse.
+fn setup_guns(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    control_scheme: Res<ControlScheme>,
+) {
+    match *game_mode {
+        GameMode::Classic => spawn_gun(
+            &mut commands,
+            Player(0),
+            0.,
+            PlayerControls::single_player().with_control_scheme(*control_scheme),
+        ),
+        GameMode::CoOp => {
+            spawn_gun(
+                &mut commands,
+                Player(0),
+                -GUN_SPACING_X,
+                PlayerControls::player_one().with_control_scheme(*control_scheme),
+            );
+            spawn_gun(
+                &mut commands,
+                Player(1),
+                GUN_SPACING_X,
+                PlayerControls::player_two(),
+            );
+        }
+        GameMode::Versus => spawn_gun(
+            &mut commands,
+            Player(0),
+            0.,
+            PlayerControls::keyboard_only().with_control_scheme(*control_scheme),
+        ),
+    }
+}
+
+/// Base, mount and barrel centered at `x`
+pub fn spawn_gun(commands: &mut Commands, player: Player, x: f32, controls: PlayerControls) {
+    spawn_gun_base(commands, player, x);
+    spawn_gun_mount(commands, player, x);
+    spawn_gun_barrel(commands, player, x, controls);
+}
+
+fn spawn_gun_base(commands: &mut Commands, player: Player, x: f32) {
     let h = GUN_BASE_Y;
     let w = GUN_BASE_X;
     let y = consts::GROUND_Y + 0.5 * h;
@@ -44,7 +111,7 @@ pub fn setup_gun_base(mut commands: Commands) {
             custom_size: Some(Vec2::new(w, h)),
             ..default()
         },
-        transform: Transform::from_translation(Vec3::new(0., y, 2.)),
+        transform: Transform::from_translation(Vec3::new(x, y, 2.)),
         ..default()
     };
     commands
@@ -61,10 +128,12 @@ pub fn setup_gun_base(mut commands: Commands) {
         })
         .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(CollisionGroups::new(Group::GROUP_4, Group::ALL))
-        .insert(GunBase);
+        .insert(GunBase)
+        .insert(player)
+        .insert(GameSession);
 }
 
-pub fn setup_gun_mount(mut commands: Commands) {
+fn spawn_gun_mount(commands: &mut Commands, player: Player, x: f32) {
     let mount_rectangle_shape = shapes::Rectangle {
         extents: Vec2::new(GUN_MOUNT_X, GUN_MOUNT_Y),
         origin: RectangleOrigin::Center,
@@ -82,7 +151,7 @@ pub fn setup_gun_mount(mut commands: Commands) {
             ..default()
         })
         .insert(Fill::color(Color::PINK))
-        .insert(Transform::from_xyz(0., rectangle_y, 2.0))
+        .insert(Transform::from_xyz(x, rectangle_y, 2.0))
         .insert(RigidBody::Fixed)
         .insert(Collider::cuboid(0.5 * GUN_MOUNT_X, 0.5 * GUN_MOUNT_Y))
         .insert(ActiveEvents::COLLISION_EVENTS)
@@ -96,10 +165,12 @@ pub fn setup_gun_mount(mut commands: Commands) {
                 .insert(Fill::color(Color::PINK))
                 .insert(Transform::from_xyz(0., GUN_MOUNT_Y / 2.0, 2.0));
         })
-        .insert(GunMount);
+        .insert(GunMount)
+        .insert(player)
+        .insert(GameSession);
 }
 
-pub fn setup_gun_barrel(mut commands: Commands) {
+fn spawn_gun_barrel(commands: &mut Commands, player: Player, x: f32, controls: PlayerControls) {
     let y = consts::GROUND_Y + GUN_BASE_Y + GUN_MOUNT_Y; //+ 0.5 * GUN_HEIGHT;
     let sprite_size = Vec2::new(GUN_WIDTH, GUN_HEIGHT);
     let sprite_bundle = SpriteBundle {
@@ -109,7 +180,7 @@ pub fn setup_gun_barrel(mut commands: Commands) {
             anchor: BottomCenter,
             ..default()
         },
-        transform: Transform::from_translation(Vec3::new(0., y, 1.)),
+        transform: Transform::from_translation(Vec3::new(x, y, 1.)),
         ..default()
     };
     commands
@@ -124,131 +195,190 @@ pub fn setup_gun_barrel(mut commands: Commands) {
             principal_inertia: 0.1,
             ..default()
         }))
-        .insert(Gun { last_fired: 0. });
+        .insert(Gun { last_fired: 0. })
+        .insert(player)
+        .insert(controls)
+        .insert(Score::default())
+        .insert(GameSession);
+}
+
+/// Turn rate that swings the barrel toward `target`, capped, never past the boundary angle
+fn mouse_aim_velocity(transform: &Transform, target: Vec2) -> f32 {
+    let offset = target - transform.translation.truncate();
+    // Barrel points along local y, so zero is straight up and positive turns left
+    let target_angle = (-offset.x)
+        .atan2(offset.y)
+        .clamp(-BOUNDARY_ANGLE, BOUNDARY_ANGLE);
+    let (gun_angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+    ((target_angle - gun_angle) * MOUSE_AIM_GAIN)
+        .clamp(-MOUSE_ANGULAR_VELOCITY, MOUSE_ANGULAR_VELOCITY)
 }
 
-/// Move gun with keyboard or gamepad, within bounds.
+/// Guns still in the fight
+type StandingGun = (With<Gun>, Without<GunDestroyed>);
+/// Any part of a gun assembly still standing
+type StandingGunPart = (
+    Or<(With<Gun>, With<GunMount>, With<GunBase>)>,
+    Without<GunDestroyed>,
+);
+/// What turns dark and loose when a gun assembly is destroyed
+type WreckablePart<'a> = (
+    Entity,
+    &'a Player,
+    Option<&'a mut Sprite>,
+    Option<&'a mut Velocity>,
+);
+/// The parts of a gun assembly a landed paratrooper can reach
+type StandingGunOrMount = (Or<(With<Gun>, With<GunMount>)>, Without<GunDestroyed>);
+
+/// Move each gun with its player's bound keys, gamepad or mouse, within bounds.
 fn move_gun(
-    gamepads: Res<Gamepads>,
-    button_inputs: Res<ButtonInput<GamepadButton>>,
-    keyboard_inputs: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(&mut Velocity, &Transform), With<Gun>>,
+    input: ActionInput,
+    cursor_position: Res<CursorPosition>,
+    mut query: Query<(&mut Velocity, &Transform, &PlayerControls), StandingGun>,
 ) {
-    // Resolve keyboard and gamepad inputs
-    let keyboard_left = keyboard_inputs.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
-    let keyboard_right = keyboard_inputs.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
-    let gamepad_right = gamepads
-        .iter()
-        .find(|&gamepad| {
-            button_inputs.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadRight))
-        })
-        .is_some();
-    let gamepad_left = gamepads
-        .iter()
-        .find(|&gamepad| {
-            button_inputs.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadLeft))
-        })
-        .is_some();
-    let any_left = keyboard_left || gamepad_left;
-    let any_right = keyboard_right || gamepad_right;
-
-    // Rotate the gun
-    let (mut velocity, transform) = query.get_single_mut().expect("Gun entity not found!");
-    let (gun_axis, gun_angle) = transform.rotation.to_axis_angle();
-    velocity.angvel = if any_left && any_right {
-        0.
-    } else if any_left && (gun_axis.z <= 0. || gun_angle < BOUNDARY_ANGLE) {
-        ANGULAR_VELOCITY
-    } else if any_right && (gun_axis.z >= 0. || gun_angle < BOUNDARY_ANGLE) {
-        -ANGULAR_VELOCITY
-    } else {
-        0.
+    for (mut velocity, transform, controls) in query.iter_mut() {
+        if let (true, Some(cursor)) = (controls.mouse, cursor_position.0) {
+            velocity.angvel = mouse_aim_velocity(transform, cursor);
+            continue;
+        }
+
+        // Positive turns left
+        let rotation = input.rotation(controls);
+
+        // Rotate the gun
+        let (gun_axis, gun_angle) = transform.rotation.to_axis_angle();
+        let within_left = gun_axis.z <= 0. || gun_angle < BOUNDARY_ANGLE;
+        let within_right = gun_axis.z >= 0. || gun_angle < BOUNDARY_ANGLE;
+        velocity.angvel = if (rotation > 0. && within_left) || (rotation < 0. && within_right) {
+            rotation * ANGULAR_VELOCITY
+        } else {
+            0.
+        }
+    }
+}
+
+/// Explode the gun and mount of `player`
+fn send_gun_explosions(
+    event_writer: &mut EventWriter<GunExplosionEvent>,
+    player: Player,
+    gun_query: &Query<(&Player, &Transform), With<Gun>>,
+    gun_mount_query: &Query<(&Player, &Transform), With<GunMount>>,
+) {
+    let gun_translations = gun_query.iter().filter(|(p, _)| **p == player);
+    let mount_translations = gun_mount_query.iter().filter(|(p, _)| **p == player);
+    for (_, transform) in gun_translations.chain(mount_translations) {
+        event_writer.send(GunExplosionEvent {
+            translation: transform.translation,
+            player,
+        });
     }
 }
 
-/// Bomb colliding with any part of gun assembly causes explosion
+/// Bomb colliding with any part of a gun assembly causes explosion
 fn gun_bomb_collision_system(
     mut event_reader: EventReader<CollisionEvent>,
     mut event_writer: EventWriter<GunExplosionEvent>,
-    gun_query: Query<(Entity, &Transform), With<Gun>>,
-    gun_mount_query: Query<(Entity, &Transform), With<GunMount>>,
-    gun_base_query: Query<(Entity, &Transform), With<GunBase>>,
+    gun_parts_query: Query<&Player, StandingGunPart>,
+    gun_query: Query<(&Player, &Transform), With<Gun>>,
+    gun_mount_query: Query<(&Player, &Transform), With<GunMount>>,
     bombs_query: Query<Entity, With<Bomb>>,
 ) {
-    let (gun_entity, gun_transform) = gun_query.get_single().expect("No gun entity.");
-    let (gun_mount_entity, gun_mount_transform) =
-        gun_mount_query.get_single().expect("No gun mount entity.");
-    let (gun_base_entity, _gun_base_transform) =
-        gun_base_query.get_single().expect("No gun base entity.");
-    let targets = HashSet::from([gun_entity, gun_mount_entity, gun_base_entity]);
     for &collision_event in event_reader.read() {
         if let CollisionEvent::Started(entity1, entity2, _flags) = collision_event {
             let is_bomb_collision = bombs_query.contains(entity1) || bombs_query.contains(entity2);
-            let is_gun_collision = targets.contains(&entity1) || targets.contains(&entity2);
-            if is_bomb_collision && is_gun_collision {
-                event_writer.send(GunExplosionEvent {
-                    translation: gun_transform.translation,
-                });
-                event_writer.send(GunExplosionEvent {
-                    translation: gun_mount_transform.translation,
-                });
+            let gun_player = gun_parts_query
+                .get(entity1)
+                .or_else(|_| gun_parts_query.get(entity2));
+            if let (true, Ok(&player)) = (is_bomb_collision, gun_player) {
+                send_gun_explosions(&mut event_writer, player, &gun_query, &gun_mount_query);
             }
         }
     }
 }
 
-/// Landed paratrooper colliding with gun or mount causes explosion
+/// Landed paratrooper colliding with a gun or mount causes explosion
 fn gun_paratrooper_collision_system(
     mut event_reader: EventReader<CollisionEvent>,
     mut event_writer: EventWriter<GunExplosionEvent>,
-    gun_query: Query<(Entity, &Transform), With<Gun>>,
-    gun_mount_query: Query<(Entity, &Transform), With<GunMount>>,
+    gun_parts_query: Query<&Player, StandingGunOrMount>,
+    gun_query: Query<(&Player, &Transform), With<Gun>>,
+    gun_mount_query: Query<(&Player, &Transform), With<GunMount>>,
     paratroopers_query: Query<Entity, With<Paratrooper>>,
 ) {
-    let (gun_entity, gun_transform) = gun_query.get_single().expect("No gun entity.");
-    let (gun_mount_entity, gun_mount_transform) =
-        gun_mount_query.get_single().expect("No gun mount entity.");
-    let targets = HashSet::from([gun_entity, gun_mount_entity]);
     for &collision_event in event_reader.read() {
         if let CollisionEvent::Started(entity1, entity2, _flags) = collision_event {
-            let is_bomb_collision =
+            let is_paratrooper_collision =
                 paratroopers_query.contains(entity1) || paratroopers_query.contains(entity2);
-            let is_gun_collision = targets.contains(&entity1) || targets.contains(&entity2);
-            if is_bomb_collision && is_gun_collision {
-                event_writer.send(GunExplosionEvent {
-                    translation: gun_transform.translation,
-                });
-                event_writer.send(GunExplosionEvent {
-                    translation: gun_mount_transform.translation,
-                });
+            let gun_player = gun_parts_query
+                .get(entity1)
+                .or_else(|_| gun_parts_query.get(entity2));
+            if let (true, Ok(&player)) = (is_paratrooper_collision, gun_player) {
+                send_gun_explosions(&mut event_writer, player, &gun_query, &gun_mount_query);
+            }
+        }
+    }
+}
+
+/// Disable and darken the gun assembly of each exploded player
+fn destroy_gun_system(
+    mut commands: Commands,
+    mut event_reader: EventReader<GunExplosionEvent>,
+    mut gun_parts_query: Query<WreckablePart, StandingGunPart>,
+) {
+    let players: HashSet<Player> = event_reader.read().map(|event| event.player).collect();
+    for (entity, player, sprite, velocity) in gun_parts_query.iter_mut() {
+        if players.contains(player) {
+            if let Some(mut sprite) = sprite {
+                sprite.color = DESTROYED_GUN_COLOR;
+            }
+            if let Some(mut velocity) = velocity {
+                velocity.angvel = 0.;
             }
+            commands.entity(entity).insert(GunDestroyed);
         }
     }
 }
 
+/// The game is over once every gun has been destroyed
+fn all_guns_destroyed_system(
+    gun_query: Query<(), StandingGun>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if gun_query.is_empty() {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+fn guns_vulnerable(god_mode: Res<GodMode>) -> bool {
+    !god_mode.0
+}
+
 /// Stop gun rotation.
 fn stop_gun(mut gun_query: Query<&mut Velocity, With<Gun>>) {
-    let mut velocity = gun_query.get_single_mut().expect("Gun velocity not found.");
-    velocity.angvel = 0.;
+    for mut velocity in gun_query.iter_mut() {
+        velocity.angvel = 0.;
+    }
 }
 
 pub struct GunPlugin;
 
 impl Plugin for GunPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(AppState::MainMenu),
-            (setup_gun_base, setup_gun_mount, setup_gun_barrel),
-        )
-        .add_systems(
-            Update,
-            (
-                move_gun,
-                gun_bomb_collision_system,
-                gun_paratrooper_collision_system,
+        app.add_systems(OnEnter(AppState::NewGame), setup_guns.after(SessionCleanup))
+            .add_systems(
+                Update,
+                (
+                    move_gun,
+                    gun_bomb_collision_system.run_if(guns_vulnerable),
+                    gun_paratrooper_collision_system.run_if(guns_vulnerable),
+                    destroy_gun_system
+                        .after(gun_bomb_collision_system)
+                        .after(gun_paratrooper_collision_system),
+                    all_guns_destroyed_system,
+                )
+                    .run_if(in_state(AppState::InGame)),
             )
-                .run_if(in_state(AppState::InGame)),
-        )
-        .add_systems(OnEnter(AppState::GameOver), stop_gun);
+            .add_systems(OnEnter(AppState::GameOver), stop_gun);
     }
 }
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::rules::GameMode;
+use crate::score::{GameClock, Score};
+use crate::settings::{read_ron_file, write_ron_file};
+use crate::AppState;
+
+const HIGH_SCORES_PATH: &str = "high_scores.ron";
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub score: i32,
+    pub mode: GameMode,
+    /// How long the defense held
+    pub duration: Duration,
+}
+
+/// Best team scores, highest first
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    /// Add a finished game. Returns its rank if it made the table.
+    fn record(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+fn load_high_scores(mut commands: Commands) {
+    commands.insert_resource(read_ron_file::<HighScores>(HIGH_SCORES_PATH));
+}
+
+/// Versus games have no defender score worth ranking
+fn record_high_score(
+    mut high_scores: ResMut<HighScores>,
+    score: Res<Score>,
+    game_clock: Res<GameClock>,
+    game_mode: Res<GameMode>,
+) {
+    if *game_mode == GameMode::Versus {
+        return;
+    }
+    let entry = HighScoreEntry {
+        score: score.total_score,
+        mode: *game_mode,
+        duration: game_clock.duration(),
+    };
+    if high_scores.record(entry).is_some() {
+        write_ron_file(HIGH_SCORES_PATH, &*high_scores);
+    }
+}
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_high_scores)
+            .add_systems(OnEnter(AppState::GameOver), record_high_score);
+    }
+}
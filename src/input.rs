@@ -0,0 +1,285 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::player::{GamepadSelection, PlayerControls};
+
+/// Fraction of left stick travel ignored around the center
+const DEFAULT_STICK_DEADZONE: f32 = 0.2;
+
+/// Something a player does, independent of the key or button that does it
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    RotateLeft,
+    RotateRight,
+    Fire,
+    Pause,
+    Confirm,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::Fire => "Fire",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+        }
+    }
+}
+
+/// Keys and gamepad buttons bound to each action
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionMap {
+    pub keys: BTreeMap<Action, Vec<KeyCode>>,
+    pub buttons: BTreeMap<Action, Vec<GamepadButtonType>>,
+}
+
+impl ActionMap {
+    fn with_keys(mut self, action: Action, keys: &[KeyCode]) -> ActionMap {
+        self.keys.insert(action, keys.to_vec());
+        self
+    }
+
+    fn with_buttons(mut self, action: Action, buttons: &[GamepadButtonType]) -> ActionMap {
+        self.buttons.insert(action, buttons.to_vec());
+        self
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Short label for the first key bound to `action`, for on-screen prompts
+    pub fn describe(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or_else(|| "?".to_string(), |&key| key_name(key))
+    }
+}
+
+/// `KeyA` reads as `A` and `Digit2` as `2`
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+pub fn button_name(button_type: GamepadButtonType) -> String {
+    format!("{:?}", button_type)
+}
+
+const GAMEPAD_ROTATE_LEFT_BUTTONS: [GamepadButtonType; 1] = [GamepadButtonType::DPadLeft];
+const GAMEPAD_ROTATE_RIGHT_BUTTONS: [GamepadButtonType; 1] = [GamepadButtonType::DPadRight];
+const GAMEPAD_FIRE_BUTTONS: [GamepadButtonType; 4] = [
+    GamepadButtonType::East,
+    GamepadButtonType::West,
+    GamepadButtonType::South,
+    GamepadButtonType::North,
+];
+
+fn gun_action_map(
+    rotate_left: &[KeyCode],
+    rotate_right: &[KeyCode],
+    fire: &[KeyCode],
+) -> ActionMap {
+    ActionMap::default()
+        .with_keys(Action::RotateLeft, rotate_left)
+        .with_keys(Action::RotateRight, rotate_right)
+        .with_keys(Action::Fire, fire)
+        .with_buttons(Action::RotateLeft, &GAMEPAD_ROTATE_LEFT_BUTTONS)
+        .with_buttons(Action::RotateRight, &GAMEPAD_ROTATE_RIGHT_BUTTONS)
+        .with_buttons(Action::Fire, &GAMEPAD_FIRE_BUTTONS)
+}
+
+/// Which set of bindings an input comes from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingSlot {
+    /// One player on the whole keyboard, also the defender in versus
+    Solo,
+    PlayerOne,
+    PlayerTwo,
+    /// Pause and confirm, from every keyboard and gamepad
+    Menu,
+}
+
+impl BindingSlot {
+    pub const ALL: [BindingSlot; 4] = [
+        BindingSlot::Solo,
+        BindingSlot::PlayerOne,
+        BindingSlot::PlayerTwo,
+        BindingSlot::Menu,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BindingSlot::Solo => "SINGLE PLAYER",
+            BindingSlot::PlayerOne => "PLAYER ONE",
+            BindingSlot::PlayerTwo => "PLAYER TWO",
+            BindingSlot::Menu => "MENUS",
+        }
+    }
+
+    /// Actions that can be bound in this slot
+    pub fn actions(&self) -> &'static [Action] {
+        match self {
+            BindingSlot::Menu => &[Action::Pause, Action::Confirm],
+            _ => &[Action::RotateLeft, Action::RotateRight, Action::Fire],
+        }
+    }
+}
+
+/// Every rebindable control, saved with the settings
+#[derive(Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub solo: ActionMap,
+    pub player_one: ActionMap,
+    pub player_two: ActionMap,
+    pub menu: ActionMap,
+    pub stick_deadzone: f32,
+}
+
+impl Default for InputBindings {
+    fn default() -> InputBindings {
+        InputBindings {
+            solo: gun_action_map(
+                &[KeyCode::KeyA, KeyCode::ArrowLeft],
+                &[KeyCode::KeyD, KeyCode::ArrowRight],
+                &[KeyCode::Space],
+            ),
+            player_one: gun_action_map(
+                &[KeyCode::KeyA],
+                &[KeyCode::KeyD],
+                &[KeyCode::KeyW, KeyCode::Space],
+            ),
+            player_two: gun_action_map(
+                &[KeyCode::ArrowLeft],
+                &[KeyCode::ArrowRight],
+                &[KeyCode::ArrowUp, KeyCode::Enter],
+            ),
+            menu: ActionMap::default()
+                .with_keys(
+                    Action::Pause,
+                    &[KeyCode::Escape, KeyCode::Pause, KeyCode::KeyP],
+                )
+                .with_keys(Action::Confirm, &[KeyCode::Enter, KeyCode::NumpadEnter])
+                .with_buttons(Action::Pause, &[GamepadButtonType::Start])
+                .with_buttons(Action::Confirm, &[GamepadButtonType::South]),
+            stick_deadzone: DEFAULT_STICK_DEADZONE,
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn map(&self, slot: BindingSlot) -> &ActionMap {
+        match slot {
+            BindingSlot::Solo => &self.solo,
+            BindingSlot::PlayerOne => &self.player_one,
+            BindingSlot::PlayerTwo => &self.player_two,
+            BindingSlot::Menu => &self.menu,
+        }
+    }
+
+    pub fn map_mut(&mut self, slot: BindingSlot) -> &mut ActionMap {
+        match slot {
+            BindingSlot::Solo => &mut self.solo,
+            BindingSlot::PlayerOne => &mut self.player_one,
+            BindingSlot::PlayerTwo => &mut self.player_two,
+            BindingSlot::Menu => &mut self.menu,
+        }
+    }
+}
+
+/// Reads actions through the current bindings
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    bindings: Res<'w, InputBindings>,
+    keyboard_inputs: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    button_inputs: Res<'w, ButtonInput<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl ActionInput<'_> {
+    fn bound_buttons(
+        &self,
+        slot: BindingSlot,
+        selection: GamepadSelection,
+        action: Action,
+    ) -> Vec<GamepadButton> {
+        let button_types = self.bindings.map(slot).buttons(action);
+        selection
+            .gamepads(&self.gamepads)
+            .into_iter()
+            .flat_map(|gamepad| {
+                button_types
+                    .iter()
+                    .map(move |&button_type| GamepadButton::new(gamepad, button_type))
+            })
+            .collect()
+    }
+
+    /// `action` is held on any of the player's keys or gamepad buttons
+    pub fn pressed(&self, controls: &PlayerControls, action: Action) -> bool {
+        let keys = self.bindings.map(controls.bindings).keys(action);
+        self.keyboard_inputs.any_pressed(keys.iter().copied())
+            || self.button_inputs.any_pressed(self.bound_buttons(
+                controls.bindings,
+                controls.gamepad,
+                action,
+            ))
+    }
+
+    /// Menu `action` was pressed this frame on any keyboard or gamepad
+    pub fn menu_just_pressed(&self, action: Action) -> bool {
+        let keys = self.bindings.menu.keys(action);
+        self.keyboard_inputs.any_just_pressed(keys.iter().copied())
+            || self.button_inputs.any_just_pressed(self.bound_buttons(
+                BindingSlot::Menu,
+                GamepadSelection::Any,
+                action,
+            ))
+    }
+
+    /// Turn demand from -1 (full right) to 1 (full left). Keys and D-pad turn at full speed,
+    /// the left stick in proportion to how far it is pushed.
+    pub fn rotation(&self, controls: &PlayerControls) -> f32 {
+        let left = self.pressed(controls, Action::RotateLeft);
+        let right = self.pressed(controls, Action::RotateRight);
+        if left || right {
+            return if left && right {
+                0.
+            } else if left {
+                1.
+            } else {
+                -1.
+            };
+        }
+
+        let deadzone = self.bindings.stick_deadzone.clamp(0., 0.95);
+        controls
+            .gamepad
+            .gamepads(&self.gamepads)
+            .into_iter()
+            .filter_map(|gamepad| {
+                self.axes
+                    .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            })
+            .filter(|x| x.abs() > deadzone)
+            // Rescale past the deadzone so turning starts from zero; pushing left turns left
+            .map(|x| -x.signum() * (x.abs() - deadzone) / (1. - deadzone))
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(0.)
+            .clamp(-1., 1.)
+    }
+}
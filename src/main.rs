@@ -4,64 +4,133 @@
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 
+use crate::animation::AnimationPlugin;
 use crate::assault::AssaultPlugin;
 use crate::audio::AudioStatePlugin;
 use crate::bomber::BomberPlugin;
+use crate::camera_effects::CameraEffectsPlugin;
 use crate::cloud::CloudPlugin;
+use crate::crosshair::CrosshairPlugin;
+use crate::daylight::DaylightPlugin;
+use crate::engine_audio::EngineAudioPlugin;
 use crate::explosion::ExplosionPlugin;
+use crate::high_score::HighScorePlugin;
+use crate::map::MapPlugin;
 use crate::menu::{AppState, MenuPlugin};
+use crate::menu_button::MenuButtonPlugin;
+use crate::mixer::MixerPlugin;
+use crate::music::MusicPlugin;
+use crate::particle::ParticlePlugin;
+use crate::rebind::RebindPlugin;
+use crate::scenario::ScenarioPlugin;
+use crate::settings::SettingsPlugin;
+use crate::settings_menu::SettingsMenuPlugin;
+use crate::voice::VoicePlugin;
+use crate::weather::WeatherPlugin;
 use aircraft::AircraftPlugin;
 use bullet::BulletPlugin;
 use events::*;
 use gun::GunPlugin;
 use paratrooper::ParatrooperPlugin;
+use rules::RulesPlugin;
 use score::ScorePlugin;
+use session::SessionPlugin;
 use terrain::TerrainPlugin;
+use versus::VersusPlugin;
 
 mod aircraft;
+mod animation;
 mod assault;
 mod audio;
 mod bomber;
 mod bullet;
+mod camera_effects;
 mod cloud;
 mod consts;
+mod crosshair;
+mod daylight;
+#[cfg(feature = "debug_overlay")]
+mod debug_overlay;
+#[cfg(feature = "dev_console")]
+mod dev_console;
+mod engine_audio;
 mod events;
 mod explosion;
 mod gun;
+mod high_score;
+mod input;
+mod map;
 mod menu;
+mod menu_button;
+mod mixer;
+mod music;
 mod paratrooper;
+mod particle;
+mod player;
+mod rebind;
+mod rules;
+mod scenario;
 mod score;
+mod session;
+mod settings;
+mod settings_menu;
 mod terrain;
+mod versus;
+mod voice;
+mod weather;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Paratrooper".to_string(),
-                resolution: (consts::WINDOW_WIDTH, consts::WINDOW_HEIGHT).into(),
-                ..default()
-            }),
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Paratrooper".to_string(),
+            resolution: (consts::WINDOW_WIDTH, consts::WINDOW_HEIGHT).into(),
             ..default()
-        }))
-        .insert_resource(ClearColor(Color::rgb(0., 0., 0.)))
-        .insert_state(AppState::MainMenu)
-        .add_plugins(ShapePlugin)
-        .add_plugins(GunPlugin)
-        .add_plugins(BulletPlugin)
-        .add_plugins(AircraftPlugin)
-        .add_plugins(BomberPlugin)
-        .add_plugins(TerrainPlugin)
-        .add_plugins(ParatrooperPlugin)
-        .add_plugins(ScorePlugin)
-        .add_plugins(AudioStatePlugin)
-        .add_plugins(ExplosionPlugin)
-        .add_plugins(MenuPlugin)
-        .add_plugins(CloudPlugin)
-        .add_plugins(EventPlugin)
-        .add_plugins(AssaultPlugin)
-        .add_systems(Startup, setup_camera)
-        .add_systems(Update, bevy::window::close_on_esc)
-        .run();
+        }),
+        ..default()
+    }))
+    .insert_resource(ClearColor(Color::rgb(0., 0., 0.)))
+    .insert_state(AppState::MainMenu)
+    .add_plugins(ShapePlugin)
+    .add_plugins(GunPlugin)
+    .add_plugins(BulletPlugin)
+    .add_plugins(AircraftPlugin)
+    .add_plugins(BomberPlugin)
+    .add_plugins(TerrainPlugin)
+    .add_plugins(ParatrooperPlugin)
+    .add_plugins(ScorePlugin)
+    .add_plugins(AudioStatePlugin)
+    .add_plugins(ExplosionPlugin)
+    .add_plugins(MenuPlugin)
+    .add_plugins(CloudPlugin)
+    .add_plugins(EventPlugin)
+    .add_plugins(AssaultPlugin)
+    .add_plugins(RulesPlugin)
+    .add_plugins(SessionPlugin)
+    .add_plugins(VersusPlugin)
+    .add_plugins(CrosshairPlugin)
+    .add_plugins(SettingsPlugin)
+    .add_plugins(RebindPlugin)
+    .add_plugins(MenuButtonPlugin)
+    .add_plugins(HighScorePlugin)
+    .add_plugins(SettingsMenuPlugin)
+    .add_plugins(MixerPlugin)
+    .add_plugins(EngineAudioPlugin)
+    .add_plugins(MusicPlugin)
+    .add_plugins(VoicePlugin)
+    .add_plugins(AnimationPlugin)
+    .add_plugins(ParticlePlugin)
+    .add_plugins(CameraEffectsPlugin)
+    .add_plugins(DaylightPlugin)
+    .add_plugins(MapPlugin)
+    .add_plugins(WeatherPlugin)
+    .add_plugins(ScenarioPlugin)
+    .add_systems(Startup, setup_camera);
+    #[cfg(feature = "debug_overlay")]
+    app.add_plugins(debug_overlay::DebugOverlayPlugin);
+    #[cfg(feature = "dev_console")]
+    app.add_plugins(dev_console::DevConsolePlugin);
+    app.run();
 }
 
 fn setup_camera(mut commands: Commands) {
@@ -0,0 +1,367 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::sprite::Anchor;
+use bevy::transform::TransformSystem;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::consts::{GROUND_Y, WINDOW_WIDTH};
+use crate::daylight::Scenery;
+use crate::terrain::Ground;
+use crate::weather::Weather;
+
+/// Extra width on each side of a layer, so the edges never show as the camera shakes
+const LAYER_MARGIN: f32 = 40.;
+const CITY_SKYLINE_WIDTH: f32 = 367.;
+
+/*
+  A map sets the scenery, clouds and weather. Every map plays the same level track, as it is the
+  only one in assets/audio; per-map music waits on more tracks.
+
+  The coast's sea is generated, like the mountains and hills. The parts of the War on Water pack
+  in assets/images are the planes, paratroopers and skyline, with no sea tiles among them.
+*/
+
+/// Where a layer's pixels come from
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum LayerArt {
+    File(&'static str),
+    /// Jagged peaks
+    Mountains,
+    /// Rolling dunes and snow drifts
+    Hills,
+    /// Blocky rooftops
+    Buildings,
+    /// Choppy sea
+    Waves,
+}
+
+/// One background layer, standing on the ground line
+#[derive(Clone, Copy, Debug)]
+struct LayerData {
+    art: LayerArt,
+    /// 0 is on the horizon and stays put, 1 moves with the world
+    depth: f32,
+    /// Tallest point in pixels. Ignored for image files, which are scaled to the window width.
+    height: f32,
+    color: Color,
+    /// Varies the shape of generated art
+    seed: u64,
+}
+
+/// Everything there is to know about a map
+#[derive(Clone, Copy, Debug)]
+pub struct MapData {
+    /// Farthest first
+    layers: &'static [LayerData],
+    pub ground_color: Color,
+    /// Cloud textures in use, numbered from 1 as on disk
+    pub clouds: &'static [usize],
+    pub cloud_color: Color,
+    /// Weather for each wave in turn, repeating once the list runs out
+    pub weather: &'static [Weather],
+}
+
+/// Level theme, chosen on the main menu
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Map {
+    #[default]
+    City,
+    Desert,
+    Arctic,
+    Coast,
+}
+
+impl Map {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Map::City => "City",
+            Map::Desert => "Desert",
+            Map::Arctic => "Arctic",
+            Map::Coast => "Coast",
+        }
+    }
+
+    pub fn next(&self) -> Map {
+        match self {
+            Map::City => Map::Desert,
+            Map::Desert => Map::Arctic,
+            Map::Arctic => Map::Coast,
+            Map::Coast => Map::City,
+        }
+    }
+
+    pub fn data(&self) -> MapData {
+        const ALL_CLOUDS: &[usize] = &[1, 2, 3, 4, 5, 6, 7];
+        match self {
+            Map::City => MapData {
+                layers: &CITY_LAYERS,
+                ground_color: Color::rgb(0., 0.68, 0.32),
+                clouds: ALL_CLOUDS,
+                cloud_color: Color::WHITE,
+                weather: &[Weather::Clear, Weather::Rain, Weather::Clear, Weather::Fog],
+            },
+            Map::Desert => MapData {
+                layers: &DESERT_LAYERS,
+                ground_color: Color::rgb(0.9, 0.76, 0.5),
+                // Thin, wispy clouds only
+                clouds: &[3, 6],
+                cloud_color: Color::rgba(1., 0.97, 0.9, 0.7),
+                weather: &[Weather::Clear],
+            },
+            Map::Arctic => MapData {
+                layers: &ARCTIC_LAYERS,
+                ground_color: Color::rgb(0.94, 0.96, 1.),
+                clouds: ALL_CLOUDS,
+                cloud_color: Color::rgb(0.8, 0.82, 0.86),
+                weather: &[Weather::Snow, Weather::Clear, Weather::Fog, Weather::Snow],
+            },
+            Map::Coast => MapData {
+                layers: &COAST_LAYERS,
+                // A beach for the troopers to land on
+                ground_color: Color::rgb(0.86, 0.78, 0.58),
+                clouds: ALL_CLOUDS,
+                cloud_color: Color::WHITE,
+                weather: &[Weather::Fog, Weather::Clear, Weather::Rain],
+            },
+        }
+    }
+}
+
+const fn layer(art: LayerArt, depth: f32, height: f32, color: Color, seed: u64) -> LayerData {
+    LayerData {
+        art,
+        depth,
+        height,
+        color,
+        seed,
+    }
+}
+
+const CITY_LAYERS: [LayerData; 3] = [
+    layer(
+        LayerArt::Mountains,
+        0.1,
+        170.,
+        Color::rgb(0.3, 0.33, 0.45),
+        1,
+    ),
+    layer(LayerArt::File("images/city4.png"), 0.4, 0., Color::WHITE, 0),
+    layer(
+        LayerArt::Buildings,
+        0.75,
+        60.,
+        Color::rgb(0.15, 0.15, 0.18),
+        2,
+    ),
+];
+const DESERT_LAYERS: [LayerData; 3] = [
+    layer(
+        LayerArt::Mountains,
+        0.1,
+        130.,
+        Color::rgb(0.6, 0.42, 0.3),
+        3,
+    ),
+    layer(LayerArt::Hills, 0.4, 90., Color::rgb(0.85, 0.68, 0.42), 4),
+    layer(LayerArt::Hills, 0.75, 45., Color::rgb(0.78, 0.6, 0.35), 5),
+];
+const ARCTIC_LAYERS: [LayerData; 2] = [
+    layer(
+        LayerArt::Mountains,
+        0.1,
+        220.,
+        Color::rgb(0.72, 0.8, 0.9),
+        6,
+    ),
+    layer(LayerArt::Hills, 0.45, 80., Color::rgb(0.85, 0.9, 0.96), 7),
+];
+/// Hills across the bay, the city on the shore and the sea breaking on the beach
+const COAST_LAYERS: [LayerData; 4] = [
+    layer(LayerArt::Hills, 0.1, 110., Color::rgb(0.3, 0.45, 0.4), 8),
+    layer(
+        LayerArt::File("images/city4.png"),
+        0.25,
+        0.,
+        Color::rgb(0.85, 0.88, 0.95),
+        0,
+    ),
+    layer(LayerArt::Waves, 0.55, 40., Color::rgb(0.1, 0.35, 0.6), 9),
+    layer(LayerArt::Waves, 0.8, 24., Color::rgb(0.15, 0.45, 0.7), 10),
+];
+
+/// Background layer, shifted against camera movement by its depth
+#[derive(Component)]
+struct ParallaxLayer {
+    depth: f32,
+    /// Resting position, with the camera centered
+    origin: Vec2,
+}
+
+/// Skyline silhouette in white, to be tinted by the sprite color. Columns are filled from the
+/// bottom up to the layer's profile.
+fn silhouette_image(art: LayerArt, width: usize, height: usize, seed: u64) -> Image {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let profile: Vec<f32> = match art {
+        LayerArt::File(_) => vec![height as f32; width],
+        LayerArt::Mountains | LayerArt::Hills | LayerArt::Waves => {
+            // A few sines at random phases, each an octave above the last. Mountains fold each
+            // sine into sharp ridges.
+            let (octaves, base_wavelength) = match art {
+                LayerArt::Mountains => (4, 300.),
+                LayerArt::Hills => (2, 450.),
+                _ => (2, 60.),
+            };
+            let waves: Vec<(f32, f32, f32)> = (0..octaves)
+                .map(|octave| {
+                    let scale = 2f32.powi(octave);
+                    let frequency = std::f32::consts::TAU * scale / base_wavelength;
+                    (
+                        frequency,
+                        rng.gen_range(0.0..std::f32::consts::TAU),
+                        1. / scale,
+                    )
+                })
+                .collect();
+            let total: f32 = waves.iter().map(|&(_, _, amplitude)| amplitude).sum();
+            (0..width)
+                .map(|x| {
+                    let sum: f32 = waves
+                        .iter()
+                        .map(|&(frequency, phase, amplitude)| {
+                            let wave = (x as f32 * frequency + phase).sin();
+                            let wave = if art == LayerArt::Mountains {
+                                1. - 2. * wave.abs()
+                            } else {
+                                wave
+                            };
+                            wave * amplitude
+                        })
+                        .sum();
+                    height as f32 * (0.6 + 0.4 * sum / total)
+                })
+                .collect()
+        }
+        LayerArt::Buildings => {
+            let mut profile = Vec::with_capacity(width);
+            while profile.len() < width {
+                let building_width = rng.gen_range(18..60);
+                let building_height = rng.gen_range(0.3..1.0) * height as f32;
+                profile.extend(std::iter::repeat_n(building_height, building_width));
+            }
+            profile.truncate(width);
+            profile
+        }
+    };
+
+    let mut data = vec![0u8; width * height * 4];
+    for (x, &top) in profile.iter().enumerate() {
+        for y in 0..height {
+            // Image rows run top down
+            if (height - y) as f32 <= top {
+                let pixel = 4 * (y * width + x);
+                data[pixel..pixel + 4].copy_from_slice(&[255, 255, 255, 255]);
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Replace the background with the selected map's, and repaint the ground
+fn spawn_map(
+    mut commands: Commands,
+    map: Res<Map>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    layer_query: Query<Entity, With<ParallaxLayer>>,
+    mut ground_query: Query<(&mut Scenery, &mut Sprite), With<Ground>>,
+) {
+    for entity in layer_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let data = map.data();
+    let width = WINDOW_WIDTH + 2. * LAYER_MARGIN;
+    for layer in data.layers {
+        let origin = Vec2::new(0., GROUND_Y);
+        let (texture, scale) = match layer.art {
+            LayerArt::File(path) => {
+                let scale = width / CITY_SKYLINE_WIDTH;
+                (asset_server.load(path), Vec3::new(scale, scale, 1.))
+            }
+            art => (
+                images.add(silhouette_image(
+                    art,
+                    width as usize,
+                    layer.height as usize,
+                    layer.seed,
+                )),
+                Vec3::ONE,
+            ),
+        };
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: layer.color,
+                    anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                texture,
+                // Nearer layers draw over farther ones, all behind the ground
+                transform: Transform::from_translation(origin.extend(layer.depth))
+                    .with_scale(scale),
+                ..default()
+            })
+            .insert(Scenery { color: layer.color })
+            .insert(ParallaxLayer {
+                depth: layer.depth,
+                origin,
+            });
+    }
+
+    for (mut scenery, mut sprite) in ground_query.iter_mut() {
+        scenery.color = data.ground_color;
+        sprite.color = data.ground_color;
+    }
+}
+
+/// Far layers move less than near ones as the camera moves
+fn parallax_system(
+    camera_query: Query<&Transform, (With<Camera2d>, Without<ParallaxLayer>)>,
+    mut layer_query: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let camera = camera.translation.truncate();
+    for (layer, mut transform) in layer_query.iter_mut() {
+        let position = layer.origin + camera * (1. - layer.depth);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Map>()
+            .add_systems(Update, spawn_map.run_if(resource_changed::<Map>))
+            .add_systems(
+                PostUpdate,
+                parallax_system.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
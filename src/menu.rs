@@ -1,13 +1,35 @@
+use crate::consts::WINDOW_WIDTH;
+use crate::daylight::DayCycle;
+use crate::high_score::HighScores;
+use crate::input::{Action, ActionInput, InputBindings};
+use crate::map::Map;
+use crate::menu_button::{spawn_menu_button, ButtonMenu, MenuButtonSet};
+use crate::rules::GameMode;
+use crate::scenario::Scenarios;
+use crate::score::get_clock_string;
+use crate::settings_menu::SettingsReturnState;
+use crate::MenuButtonPressed;
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::window::WindowFocused;
 use bevy_rapier2d::prelude::RapierConfiguration;
 
 #[derive(PartialEq, Default, Debug, Clone, Eq, Hash, Resource, States)]
 pub enum AppState {
     #[default]
     MainMenu,
+    /// Transient state that sets up a fresh game session before `InGame`
+    NewGame,
     InGame,
     GameOver,
     Paused,
+    /// Audio, video and gameplay options, reached from the main menu or the pause menu
+    Settings,
+    /// Rebinding controls, reached from the settings screen
+    Bindings,
+    HighScores,
+    Credits,
 }
 
 #[derive(Component)]
@@ -16,9 +38,24 @@ pub struct TitleText;
 #[derive(Component)]
 pub struct PauseText;
 
+const INFO_FONT_SIZE: f32 = 28.0;
+
+const CREDITS: [&str; 7] = [
+    "Graphics: War on Water GFX",
+    "opengameart.org/content/war-on-water-gfx",
+    "Sound effects: 512 Sound Effects (8-bit style)",
+    "opengameart.org/content/512-sound-effects-8-bit-style",
+    "Font: Fira Sans",
+    "",
+    "Made with Bevy and Rapier",
+];
+
+/// Altitude and speed of each aircraft behind the menus
+const MENU_BACKGROUND_AIRCRAFT: [(f32, f32); 3] = [(300., 70.), (140., -45.), (-120., 30.)];
+
 #[derive(Resource)]
-struct FontHandles {
-    handle: Handle<Font>,
+pub struct FontHandles {
+    pub handle: Handle<Font>,
 }
 
 fn load_fonts(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -27,7 +64,7 @@ fn load_fonts(mut commands: Commands, asset_server: Res<AssetServer>) {
     });
 }
 
-/// Draw a big text sprite in the top middle
+/// Draw the title and the main menu buttons
 fn setup_title_screen(mut commands: Commands, font_handles: Res<FontHandles>) {
     commands
         .spawn(TextBundle {
@@ -49,30 +86,338 @@ fn setup_title_screen(mut commands: Commands, font_handles: Res<FontHandles>) {
             ..default()
         })
         .insert(TitleText);
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(352.0),
+                top: Val::Px(185.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(12.),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ButtonMenu::default())
+        .insert(TitleText)
+        .with_children(|parent| {
+            for (index, button) in MainMenuButton::ALL.into_iter().enumerate() {
+                spawn_menu_button(parent, font_handles.handle.clone(), button.label(), index)
+                    .insert(button);
+            }
+        });
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum MainMenuButton {
+    Start,
+    GameMode,
+    Map,
+    DayCycle,
+    Scenario,
+    HighScores,
+    Settings,
+    Credits,
+    Quit,
+}
+
+impl MainMenuButton {
+    const ALL: [MainMenuButton; 9] = [
+        MainMenuButton::Start,
+        MainMenuButton::GameMode,
+        MainMenuButton::Map,
+        MainMenuButton::DayCycle,
+        MainMenuButton::Scenario,
+        MainMenuButton::HighScores,
+        MainMenuButton::Settings,
+        MainMenuButton::Credits,
+        MainMenuButton::Quit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MainMenuButton::Start => "Start",
+            MainMenuButton::GameMode => "Mode",
+            MainMenuButton::Map => "Map",
+            MainMenuButton::DayCycle => "Time",
+            MainMenuButton::Scenario => "Level",
+            MainMenuButton::HighScores => "High Scores",
+            MainMenuButton::Settings => "Settings",
+            MainMenuButton::Credits => "Credits",
+            MainMenuButton::Quit => "Quit",
+        }
+    }
+}
+
+/// Choices on the main menu for how the next game starts
+#[derive(SystemParam)]
+struct NextGame<'w> {
+    game_mode: ResMut<'w, GameMode>,
+    map: ResMut<'w, Map>,
+    day_cycle: ResMut<'w, DayCycle>,
+    scenarios: ResMut<'w, Scenarios>,
+}
+
+fn main_menu_system(
+    mut commands: Commands,
+    mut event_reader: EventReader<MenuButtonPressed>,
+    button_query: Query<&MainMenuButton>,
+    mut next_game: NextGame,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for event in event_reader.read() {
+        let Ok(button) = button_query.get(event.0) else {
+            continue;
+        };
+        match button {
+            MainMenuButton::Start => next_state.set(AppState::NewGame),
+            MainMenuButton::GameMode => *next_game.game_mode = next_game.game_mode.next(),
+            MainMenuButton::Map => *next_game.map = next_game.map.next(),
+            MainMenuButton::DayCycle => *next_game.day_cycle = next_game.day_cycle.next(),
+            MainMenuButton::Scenario => next_game.scenarios.select_next(),
+            MainMenuButton::HighScores => next_state.set(AppState::HighScores),
+            MainMenuButton::Settings => {
+                commands.insert_resource(SettingsReturnState(AppState::MainMenu));
+                next_state.set(AppState::Settings);
+            }
+            MainMenuButton::Credits => next_state.set(AppState::Credits),
+            MainMenuButton::Quit => {
+                app_exit.send(AppExit);
+            }
+        }
+    }
+}
+
+/// The mode, map, time and level buttons show how the next game starts
+fn update_game_mode_label(
+    game_mode: Res<GameMode>,
+    map: Res<Map>,
+    day_cycle: Res<DayCycle>,
+    scenarios: Res<Scenarios>,
+    button_query: Query<(&MainMenuButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children) in button_query.iter() {
+        let label = match button {
+            MainMenuButton::GameMode => format!("Mode: {}", game_mode.name()),
+            MainMenuButton::Map => format!("Map: {}", map.name()),
+            MainMenuButton::DayCycle => format!("Time: {}", day_cycle.name()),
+            MainMenuButton::Scenario => format!("Level: {}", scenarios.name()),
+            _ => continue,
+        };
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value.clone_from(&label);
+        }
+    }
 }
 
 fn despawn_title_screen(mut commands: Commands, query: Query<Entity, With<TitleText>>) {
     for title_text in query.iter() {
-        commands.entity(title_text).despawn();
+        commands.entity(title_text).despawn_recursive();
+    }
+}
+
+/// High scores and credits: a title, some lines of text and a Back button
+#[derive(Component)]
+struct InfoScreen;
+
+#[derive(Component)]
+struct BackButton;
+
+fn spawn_info_screen(commands: &mut Commands, font: Handle<Font>, title: &str, lines: &[String]) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(InfoScreen)
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(ButtonMenu::default())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        title,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 75.0,
+                            color: Color::RED,
+                        },
+                    ));
+                    for line in lines {
+                        parent.spawn(TextBundle::from_section(
+                            line.clone(),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: INFO_FONT_SIZE,
+                                color: Color::GOLD,
+                            },
+                        ));
+                    }
+                    spawn_menu_button(parent, font.clone(), "Back", 0).insert(BackButton);
+                });
+        });
+}
+
+fn spawn_high_scores_screen(
+    mut commands: Commands,
+    font_handles: Res<FontHandles>,
+    high_scores: Res<HighScores>,
+) {
+    let lines: Vec<String> = if high_scores.entries.is_empty() {
+        vec!["No scores yet".to_string()]
+    } else {
+        high_scores
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(rank, entry)| {
+                format!(
+                    "{:>2}.   {:05}   {}   {}",
+                    rank + 1,
+                    entry.score,
+                    entry.mode.name(),
+                    get_clock_string(entry.duration)
+                )
+            })
+            .collect()
+    };
+    spawn_info_screen(
+        &mut commands,
+        font_handles.handle.clone(),
+        "HIGH SCORES",
+        &lines,
+    );
+}
+
+fn spawn_credits_screen(mut commands: Commands, font_handles: Res<FontHandles>) {
+    let lines = CREDITS.map(String::from);
+    spawn_info_screen(
+        &mut commands,
+        font_handles.handle.clone(),
+        "CREDITS",
+        &lines,
+    );
+}
+
+/// Back button, Esc or a gamepad's East button return to the title screen
+fn info_screen_system(
+    mut event_reader: EventReader<MenuButtonPressed>,
+    back_query: Query<(), With<BackButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    button_inputs: Res<ButtonInput<GamepadButton>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let back_pressed = event_reader
+        .read()
+        .any(|event| back_query.contains(event.0));
+    let gamepad_back = button_inputs
+        .get_just_pressed()
+        .any(|button| button.button_type == GamepadButtonType::East);
+    if back_pressed || gamepad_back || keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+fn despawn_info_screen(mut commands: Commands, query: Query<Entity, With<InfoScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Aircraft drifting across behind the menus
+#[derive(Component)]
+struct MenuBackground {
+    velocity: f32,
+}
+
+fn spawn_menu_background(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(), With<MenuBackground>>,
+) {
+    if !query.is_empty() {
+        return;
+    }
+    let texture = asset_server.load("images/paraplane1.png");
+    for (y, velocity) in MENU_BACKGROUND_AIRCRAFT {
+        commands
+            .spawn(SpriteBundle {
+                texture: texture.clone(),
+                sprite: Sprite {
+                    flip_x: velocity < 0.,
+                    color: Color::rgba(1., 1., 1., 0.6),
+                    ..default()
+                },
+                transform: Transform::from_xyz(-velocity * 4., y, 1.)
+                    .with_scale(Vec3::new(0.2, 0.2, 1.)),
+                ..default()
+            })
+            .insert(MenuBackground { velocity });
+    }
+}
+
+/// Fly across and wrap around at the edges
+fn animate_menu_background(time: Res<Time>, mut query: Query<(&MenuBackground, &mut Transform)>) {
+    let half_width = WINDOW_WIDTH / 2. + 60.;
+    for (background, mut transform) in query.iter_mut() {
+        transform.translation.x += background.velocity * time.delta_seconds();
+        if transform.translation.x > half_width {
+            transform.translation.x = -half_width;
+        } else if transform.translation.x < -half_width {
+            transform.translation.x = half_width;
+        }
+    }
+}
+
+fn despawn_menu_background(mut commands: Commands, query: Query<Entity, With<MenuBackground>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
     }
 }
 
-fn any_key_listener(
+/// Play again with Confirm, or go back to the title screen with Esc or a gamepad's Select
+fn game_over_listener(
+    input: ActionInput,
     button_inputs: Res<ButtonInput<GamepadButton>>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    let keyboard_any = keyboard_input.get_just_pressed().count() > 0;
-    let gamepad_any = button_inputs.get_just_pressed().count() > 0;
-    if keyboard_any || gamepad_any {
-        next_state.set(AppState::InGame);
+    let gamepad_back = button_inputs
+        .get_just_pressed()
+        .any(|button| button.button_type == GamepadButtonType::Select);
+    if input.menu_just_pressed(Action::Confirm) {
+        next_state.set(AppState::NewGame);
+    } else if gamepad_back || keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::MainMenu);
     }
 }
 
 #[derive(Component)]
 pub struct ContinueText;
 
-fn spawn_game_over_text(mut commands: Commands, font_handles: Res<FontHandles>) {
+fn spawn_game_over_text(
+    mut commands: Commands,
+    font_handles: Res<FontHandles>,
+    bindings: Res<InputBindings>,
+) {
     commands.spawn((
         TextBundle {
             style: Style {
@@ -82,14 +427,27 @@ fn spawn_game_over_text(mut commands: Commands, font_handles: Res<FontHandles>)
                 bottom: Val::Px(250.0),
                 ..default()
             },
-            text: Text::from_section(
-                "Press the ANY key to continue.",
-                TextStyle {
-                    font: font_handles.handle.clone(),
-                    font_size: 75.0,
-                    color: Color::RED,
-                },
-            ),
+            text: Text::from_sections([
+                TextSection::new(
+                    format!(
+                        "Press {} to continue.\n",
+                        bindings.menu.describe(Action::Confirm)
+                    ),
+                    TextStyle {
+                        font: font_handles.handle.clone(),
+                        font_size: 75.0,
+                        color: Color::RED,
+                    },
+                ),
+                TextSection::new(
+                    "Esc or Select: main menu",
+                    TextStyle {
+                        font: font_handles.handle.clone(),
+                        font_size: 40.0,
+                        color: Color::RED,
+                    },
+                ),
+            ]),
             ..default()
         },
         ContinueText,
@@ -102,41 +460,104 @@ fn despawn_game_over_text(mut commands: Commands, query: Query<Entity, With<Cont
     }
 }
 
-/// Pause the game, only while in game
+/// Toggle pause, only while in game
 fn pause_listener(
-    state: ResMut<State<AppState>>,
+    state: Res<State<AppState>>,
     mut next_state: ResMut<NextState<AppState>>,
-    gamepads: Res<Gamepads>,
-    button_inputs: Res<ButtonInput<GamepadButton>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut rapier_configuration: ResMut<RapierConfiguration>,
+    input: ActionInput,
 ) {
-    let keyboard_pause = keyboard_input.just_pressed(KeyCode::Pause);
-    let gamepad_pause = gamepads
-        .iter()
-        .find(|&gamepad| {
-            button_inputs.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))
-        })
-        .is_some();
-    if keyboard_pause || gamepad_pause {
+    if input.menu_just_pressed(Action::Pause) {
         match state.get() {
-            AppState::Paused => {
-                // Unpause
-                rapier_configuration.physics_pipeline_active = true;
-                rapier_configuration.query_pipeline_active = true;
-                next_state.set(AppState::InGame);
-            }
-            AppState::InGame => {
-                // Pause
-                rapier_configuration.physics_pipeline_active = false;
-                rapier_configuration.query_pipeline_active = false;
-                next_state.set(AppState::Paused);
-            }
+            AppState::Paused => next_state.set(AppState::InGame),
+            AppState::InGame => next_state.set(AppState::Paused),
             _ => (),
         };
     }
 }
 
+/// Pausing the game time freezes every timer, cooldown, clock and animation; physics stops too.
+fn pause_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier_configuration: ResMut<RapierConfiguration>,
+) {
+    time.pause();
+    rapier_configuration.physics_pipeline_active = false;
+    rapier_configuration.query_pipeline_active = false;
+}
+
+fn resume_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier_configuration: ResMut<RapierConfiguration>,
+) {
+    time.unpause();
+    rapier_configuration.physics_pipeline_active = true;
+    rapier_configuration.query_pipeline_active = true;
+}
+
+/// The Confirm press that chose Resume is still held, and may also be bound to a gun's Fire.
+/// Release it so the gun doesn't fire as play resumes.
+fn release_confirm(
+    bindings: Res<InputBindings>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut button_inputs: ResMut<ButtonInput<GamepadButton>>,
+) {
+    for &key in bindings.menu.keys(Action::Confirm) {
+        if keyboard_input.pressed(key) {
+            keyboard_input.release(key);
+        }
+    }
+    let confirm_buttons = bindings.menu.buttons(Action::Confirm);
+    let held: Vec<GamepadButton> = button_inputs
+        .get_pressed()
+        .filter(|button| confirm_buttons.contains(&button.button_type))
+        .copied()
+        .collect();
+    for button in held {
+        button_inputs.release(button);
+    }
+}
+
+/// Run condition for systems that should stop while the game is paused
+pub fn game_time_running(time: Res<Time<Virtual>>) -> bool {
+    !time.is_paused()
+}
+
+/// Pause when the window loses focus
+fn focus_listener(
+    mut event_reader: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if event_reader.read().any(|event| !event.focused) {
+        next_state.set(AppState::Paused);
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum PauseMenuButton {
+    Resume,
+    Restart,
+    Settings,
+    QuitToMenu,
+}
+
+impl PauseMenuButton {
+    const ALL: [PauseMenuButton; 4] = [
+        PauseMenuButton::Resume,
+        PauseMenuButton::Restart,
+        PauseMenuButton::Settings,
+        PauseMenuButton::QuitToMenu,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PauseMenuButton::Resume => "Resume",
+            PauseMenuButton::Restart => "Restart",
+            PauseMenuButton::Settings => "Settings",
+            PauseMenuButton::QuitToMenu => "Quit to Menu",
+        }
+    }
+}
+
 fn spawn_pause_ui(mut commands: Commands, fonts: Res<FontHandles>) {
     commands
         .spawn(NodeBundle {
@@ -145,33 +566,64 @@ fn spawn_pause_ui(mut commands: Commands, fonts: Res<FontHandles>) {
                 height: Val::Percent(100.),
                 position_type: PositionType::Absolute,
                 justify_content: JustifyContent::Center,
-                align_items: AlignItems::FlexEnd,
+                align_items: AlignItems::Center,
                 ..default()
             },
-            background_color: Color::NONE.into(),
+            background_color: Color::rgba(0., 0., 0., 0.5).into(),
             ..default()
         })
         .with_children(|parent| {
-            parent.spawn(TextBundle {
-                style: Style {
-                    align_self: AlignSelf::Center,
-                    position_type: PositionType::Absolute,
-                    ..default()
-                },
-                text: Text::from_section(
-                    "PAUSED",
-                    TextStyle {
-                        font: fonts.handle.clone(),
-                        font_size: 75.0,
-                        color: Color::BLUE,
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.),
+                        ..default()
                     },
-                ),
-                ..default()
-            });
+                    ..default()
+                })
+                .insert(ButtonMenu::default())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "PAUSED",
+                        TextStyle {
+                            font: fonts.handle.clone(),
+                            font_size: 75.0,
+                            color: Color::BLUE,
+                        },
+                    ));
+                    for (index, button) in PauseMenuButton::ALL.into_iter().enumerate() {
+                        spawn_menu_button(parent, fonts.handle.clone(), button.label(), index)
+                            .insert(button);
+                    }
+                });
         })
         .insert(PauseText);
 }
 
+fn pause_menu_system(
+    mut commands: Commands,
+    mut event_reader: EventReader<MenuButtonPressed>,
+    button_query: Query<&PauseMenuButton>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in event_reader.read() {
+        let Ok(button) = button_query.get(event.0) else {
+            continue;
+        };
+        match button {
+            PauseMenuButton::Resume => next_state.set(AppState::InGame),
+            PauseMenuButton::Restart => next_state.set(AppState::NewGame),
+            PauseMenuButton::Settings => {
+                commands.insert_resource(SettingsReturnState(AppState::Paused));
+                next_state.set(AppState::Settings);
+            }
+            PauseMenuButton::QuitToMenu => next_state.set(AppState::MainMenu),
+        }
+    }
+}
+
 fn despawn_pause_ui(mut commands: Commands, query: Query<Entity, With<PauseText>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
@@ -186,8 +638,28 @@ impl Plugin for MenuPlugin {
             .add_systems(OnEnter(AppState::MainMenu), setup_title_screen)
             .add_systems(
                 Update,
-                any_key_listener
-                    .run_if(in_state(AppState::MainMenu).or_else(in_state(AppState::GameOver))),
+                (
+                    main_menu_system.after(MenuButtonSet),
+                    update_game_mode_label,
+                )
+                    .run_if(in_state(AppState::MainMenu)),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), spawn_menu_background)
+            .add_systems(Update, animate_menu_background)
+            .add_systems(OnEnter(AppState::NewGame), despawn_menu_background)
+            .add_systems(OnEnter(AppState::HighScores), spawn_high_scores_screen)
+            .add_systems(OnEnter(AppState::Credits), spawn_credits_screen)
+            .add_systems(
+                Update,
+                info_screen_system
+                    .after(MenuButtonSet)
+                    .run_if(in_state(AppState::HighScores).or_else(in_state(AppState::Credits))),
+            )
+            .add_systems(OnExit(AppState::HighScores), despawn_info_screen)
+            .add_systems(OnExit(AppState::Credits), despawn_info_screen)
+            .add_systems(
+                Update,
+                game_over_listener.run_if(in_state(AppState::GameOver)),
             )
             .add_systems(
                 Update,
@@ -197,7 +669,19 @@ impl Plugin for MenuPlugin {
             .add_systems(OnExit(AppState::MainMenu), despawn_title_screen)
             .add_systems(OnEnter(AppState::GameOver), spawn_game_over_text)
             .add_systems(OnExit(AppState::GameOver), despawn_game_over_text)
-            .add_systems(OnEnter(AppState::Paused), spawn_pause_ui)
-            .add_systems(OnExit(AppState::Paused), despawn_pause_ui);
+            .add_systems(
+                Update,
+                pause_menu_system
+                    .after(MenuButtonSet)
+                    .run_if(in_state(AppState::Paused)),
+            )
+            .add_systems(Update, focus_listener.run_if(in_state(AppState::InGame)))
+            .add_systems(OnEnter(AppState::Paused), (spawn_pause_ui, pause_game))
+            .add_systems(OnEnter(AppState::InGame), resume_game)
+            .add_systems(OnEnter(AppState::MainMenu), resume_game)
+            .add_systems(
+                OnExit(AppState::Paused),
+                (despawn_pause_ui, release_confirm),
+            );
     }
 }
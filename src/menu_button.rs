@@ -0,0 +1,160 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionInput};
+use crate::MenuButtonPressed;
+
+const BUTTON_WIDTH: f32 = 320.;
+const BUTTON_PADDING: f32 = 20.;
+pub const BUTTON_HEIGHT: f32 = 56.;
+const BUTTON_FONT_SIZE: f32 = 36.;
+const NORMAL_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED_COLOR: Color = Color::rgb(0.55, 0.1, 0.1);
+
+/// Vertical list of buttons, navigated with the arrow keys, D-pad or mouse. Buttons are its
+/// direct children.
+#[derive(Component, Default)]
+pub struct ButtonMenu {
+    pub selected: usize,
+}
+
+/// Position of a button within its `ButtonMenu`
+#[derive(Component)]
+pub struct MenuButton {
+    pub index: usize,
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuButtonSet;
+
+/// Spawn a labelled button into a `ButtonMenu`
+pub fn spawn_menu_button<'a>(
+    parent: &'a mut ChildBuilder,
+    font: Handle<Font>,
+    label: &str,
+    index: usize,
+) -> EntityCommands<'a> {
+    let mut button = parent.spawn(ButtonBundle {
+        style: Style {
+            min_width: Val::Px(BUTTON_WIDTH),
+            height: Val::Px(BUTTON_HEIGHT),
+            padding: UiRect::horizontal(Val::Px(BUTTON_PADDING)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: NORMAL_COLOR.into(),
+        ..default()
+    });
+    button.insert(MenuButton { index }).with_children(|button| {
+        button.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font,
+                font_size: BUTTON_FONT_SIZE,
+                color: Color::WHITE,
+            },
+        ));
+    });
+    button
+}
+
+/// Up and down move the selection, Confirm presses the selected button
+fn menu_navigation_system(
+    input: ActionInput,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    button_inputs: Res<ButtonInput<GamepadButton>>,
+    mut menu_query: Query<(&mut ButtonMenu, &Children)>,
+    button_query: Query<&MenuButton>,
+    mut event_writer: EventWriter<MenuButtonPressed>,
+) {
+    let gamepad_pressed = |button_type: GamepadButtonType| {
+        button_inputs
+            .get_just_pressed()
+            .any(|button| button.button_type == button_type)
+    };
+    let up =
+        keyboard_input.just_pressed(KeyCode::ArrowUp) || gamepad_pressed(GamepadButtonType::DPadUp);
+    let down = keyboard_input.just_pressed(KeyCode::ArrowDown)
+        || gamepad_pressed(GamepadButtonType::DPadDown);
+    let confirm = input.menu_just_pressed(Action::Confirm);
+
+    for (mut menu, children) in menu_query.iter_mut() {
+        let buttons: Vec<(Entity, usize)> = children
+            .iter()
+            .filter_map(|&child| {
+                button_query
+                    .get(child)
+                    .ok()
+                    .map(|button| (child, button.index))
+            })
+            .collect();
+        let count = buttons.len();
+        if count == 0 {
+            continue;
+        }
+        if up {
+            menu.selected = (menu.selected + count - 1) % count;
+        }
+        if down {
+            menu.selected = (menu.selected + 1) % count;
+        }
+        if confirm {
+            if let Some(&(entity, _)) = buttons.iter().find(|(_, index)| *index == menu.selected) {
+                event_writer.send(MenuButtonPressed(entity));
+            }
+        }
+    }
+}
+
+/// Hovering selects a button and clicking presses it
+fn menu_mouse_system(
+    interaction_query: Query<(Entity, &Interaction, &MenuButton, &Parent), Changed<Interaction>>,
+    mut menu_query: Query<&mut ButtonMenu>,
+    mut event_writer: EventWriter<MenuButtonPressed>,
+) {
+    for (entity, interaction, button, parent) in interaction_query.iter() {
+        if *interaction == Interaction::None {
+            continue;
+        }
+        if let Ok(mut menu) = menu_query.get_mut(parent.get()) {
+            menu.selected = button.index;
+        }
+        if *interaction == Interaction::Pressed {
+            event_writer.send(MenuButtonPressed(entity));
+        }
+    }
+}
+
+fn highlight_menu_buttons(
+    menu_query: Query<(&ButtonMenu, &Children)>,
+    mut button_query: Query<(&MenuButton, &mut BackgroundColor)>,
+) {
+    for (menu, children) in menu_query.iter() {
+        for &child in children.iter() {
+            if let Ok((button, mut color)) = button_query.get_mut(child) {
+                *color = if button.index == menu.selected {
+                    SELECTED_COLOR.into()
+                } else {
+                    NORMAL_COLOR.into()
+                };
+            }
+        }
+    }
+}
+
+pub struct MenuButtonPlugin;
+
+impl Plugin for MenuButtonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (menu_navigation_system, menu_mouse_system),
+                highlight_menu_buttons,
+            )
+                .chain()
+                .in_set(MenuButtonSet),
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::audio::{SpatialAudioSink, Volume};
+use bevy::prelude::*;
+
+use crate::settings::GameSettings;
+use crate::{ExplosionEvent, ExplosionType, GunExplosionEvent};
+
+/// How far music drops under the base explosion
+const BASE_EXPLOSION_DUCK: f32 = 0.2;
+/// How far music drops under a bomb hitting the ground
+const BOMB_EXPLOSION_DUCK: f32 = 0.6;
+/// Ducked music recovers at this much volume per second
+const DUCK_RECOVERY: f32 = 0.5;
+
+/// Mixer bus a sound plays on
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Channel {
+    Music,
+    Sfx,
+    /// Screams
+    Voice,
+}
+
+/// Tags a sound with its bus. `volume` is the sound's own level before mixing.
+#[derive(Component)]
+pub struct MixerChannel {
+    pub channel: Channel,
+    pub volume: f32,
+}
+
+impl MixerChannel {
+    pub fn new(channel: Channel, volume: f32) -> MixerChannel {
+        MixerChannel { channel, volume }
+    }
+}
+
+/// Channel volumes, taken from the settings, plus music ducking
+#[derive(Resource)]
+pub struct Mixer {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub voice: f32,
+    /// Music attenuation, 1 when not ducked
+    duck: f32,
+}
+
+impl Default for Mixer {
+    fn default() -> Mixer {
+        Mixer {
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+            voice: 1.0,
+            duck: 1.0,
+        }
+    }
+}
+
+impl Mixer {
+    /// Gain of a channel after the master volume
+    pub fn gain(&self, channel: Channel) -> f32 {
+        self.master
+            * match channel {
+                Channel::Music => self.music * self.duck,
+                Channel::Sfx => self.sfx,
+                Channel::Voice => self.voice,
+            }
+    }
+
+    /// Volume to start a sound at, so it doesn't blare for a frame before the mixer catches up
+    pub fn volume(&self, channel: Channel, volume: f32) -> Volume {
+        Volume::new(volume * self.gain(channel))
+    }
+
+    /// Drop music to `level`, unless it is already ducked further
+    pub fn duck(&mut self, level: f32) {
+        self.duck = self.duck.min(level);
+    }
+}
+
+fn sync_mixer(settings: Res<GameSettings>, mut mixer: ResMut<Mixer>) {
+    mixer.master = settings.master_volume;
+    mixer.music = settings.music_volume;
+    mixer.sfx = settings.sfx_volume;
+    mixer.voice = settings.voice_volume;
+}
+
+fn duck_music(
+    mut mixer: ResMut<Mixer>,
+    mut gun_explosion_events: EventReader<GunExplosionEvent>,
+    mut explosion_events: EventReader<ExplosionEvent>,
+) {
+    if !gun_explosion_events.is_empty() {
+        gun_explosion_events.clear();
+        mixer.duck(BASE_EXPLOSION_DUCK);
+    }
+    if explosion_events
+        .read()
+        .any(|event| event.explosion_type == ExplosionType::Bomb)
+    {
+        mixer.duck(BOMB_EXPLOSION_DUCK);
+    }
+}
+
+fn recover_music(time: Res<Time>, mut mixer: ResMut<Mixer>) {
+    if mixer.duck < 1.0 {
+        mixer.duck = (mixer.duck + DUCK_RECOVERY * time.delta_seconds()).min(1.0);
+    }
+}
+
+/// Push the mixer to every playing sound when it changes, and to sounds that just started or
+/// changed level
+fn apply_mixer(
+    mixer: Res<Mixer>,
+    query: Query<(Ref<MixerChannel>, Ref<AudioSink>)>,
+    spatial_query: Query<(Ref<MixerChannel>, Ref<SpatialAudioSink>)>,
+) {
+    let volume =
+        |mixer_channel: &MixerChannel| mixer_channel.volume * mixer.gain(mixer_channel.channel);
+    for (mixer_channel, audio_sink) in query.iter() {
+        if mixer.is_changed() || mixer_channel.is_changed() || audio_sink.is_added() {
+            audio_sink.set_volume(volume(&mixer_channel));
+        }
+    }
+    for (mixer_channel, audio_sink) in spatial_query.iter() {
+        if mixer.is_changed() || mixer_channel.is_changed() || audio_sink.is_added() {
+            audio_sink.set_volume(volume(&mixer_channel));
+        }
+    }
+}
+
+pub struct MixerPlugin;
+
+impl Plugin for MixerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Mixer>().add_systems(
+            PostUpdate,
+            (
+                sync_mixer.run_if(resource_changed::<GameSettings>),
+                (duck_music, recover_music),
+                apply_mixer,
+            )
+                .chain(),
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use bevy::audio::{AddAudioSource, AudioSink, Decodable, PlaybackMode, Source};
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use crate::aircraft::Aircraft;
+use crate::bomber::{Bomb, Bomber};
+use crate::mixer::{Channel, Mixer, MixerChannel};
+use crate::paratrooper::{Paratrooper, ParatrooperState};
+use crate::{AppState, WaveStartEvent};
+
+const SAMPLE_RATE: u32 = 44_100;
+const TEMPO: f32 = 120.;
+/// Intensity change per second as the threat rises, and as it falls away
+const INTENSITY_RISE: f32 = 0.3;
+const INTENSITY_FALL: f32 = 0.1;
+/// How much each threat on screen adds to the intensity, which runs from 0 to 1
+const LANDED_THREAT: f32 = 0.2;
+const BOMB_THREAT: f32 = 0.2;
+const AIRCRAFT_THREAT: f32 = 0.08;
+
+/*
+  The level track always plays. Two synthesized drum layers fade in over it as the threat on
+  screen grows, and the track itself backs off a little to make room. Stingers mark the start
+  of each wave and the end of the game.
+*/
+
+/// Looping percussion: a pitched drum on the set steps of a 16 step bar
+#[derive(Asset, TypePath, Clone, Copy)]
+pub struct DrumLoop {
+    /// One bit per sixteenth note, first step in the lowest bit
+    pattern: u16,
+    /// Drum pitch in Hz
+    pitch: f32,
+    /// How quickly each hit dies away
+    decay: f32,
+}
+
+pub struct DrumDecoder {
+    drums: DrumLoop,
+    sample: u32,
+    phase: f32,
+}
+
+impl Decodable for DrumLoop {
+    type DecoderItem = f32;
+    type Decoder = DrumDecoder;
+
+    fn decoder(&self) -> DrumDecoder {
+        DrumDecoder {
+            drums: *self,
+            sample: 0,
+            phase: 0.,
+        }
+    }
+}
+
+impl Iterator for DrumDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let samples_per_step = (SAMPLE_RATE as f32 * 60. / TEMPO / 4.) as u32;
+        let step = (self.sample / samples_per_step) % 16;
+        let step_sample = self.sample % samples_per_step;
+        let t = step_sample as f32 / SAMPLE_RATE as f32;
+        self.sample = self.sample.wrapping_add(1);
+        if step_sample == 0 {
+            self.phase = 0.;
+        }
+        if self.drums.pattern & (1 << step) == 0 {
+            return Some(0.);
+        }
+        // Pitch drops quickly after the hit, like a struck skin
+        let frequency = self.drums.pitch * (1. + 2. * (-t * 40.).exp());
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+        Some((TAU * self.phase).sin() * (-t * self.drums.decay).exp())
+    }
+}
+
+impl Source for DrumDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Short run of notes played once
+#[derive(Asset, TypePath, Clone, Copy)]
+pub struct Stinger {
+    /// Note frequencies in Hz, played in order
+    notes: &'static [f32],
+    /// Seconds per note, the last one rings on for twice as long
+    note_length: f32,
+}
+
+impl Stinger {
+    fn samples(&self) -> u32 {
+        ((self.notes.len() + 1) as f32 * self.note_length * SAMPLE_RATE as f32) as u32
+    }
+}
+
+pub struct StingerDecoder {
+    stinger: Stinger,
+    sample: u32,
+}
+
+impl Decodable for Stinger {
+    type DecoderItem = f32;
+    type Decoder = StingerDecoder;
+
+    fn decoder(&self) -> StingerDecoder {
+        StingerDecoder {
+            stinger: *self,
+            sample: 0,
+        }
+    }
+}
+
+impl Iterator for StingerDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.stinger.samples() {
+            return None;
+        }
+        let time = self.sample as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+        let notes = self.stinger.notes;
+        let index = ((time / self.stinger.note_length) as usize).min(notes.len() - 1);
+        let t = time - index as f32 * self.stinger.note_length;
+        let frequency = notes[index];
+        // Brassy: the fundamental plus a third harmonic
+        let tone = (TAU * frequency * t).sin() + (TAU * 3. * frequency * t).sin() / 3.;
+        Some(0.5 * tone * (-t * 3.).exp())
+    }
+}
+
+impl Source for StingerDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.stinger.samples() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+#[derive(Resource)]
+struct MusicAssets {
+    level: Handle<AudioSource>,
+    drums: Handle<DrumLoop>,
+    battle_drums: Handle<DrumLoop>,
+    wave_stinger: Handle<Stinger>,
+    game_over_stinger: Handle<Stinger>,
+}
+
+/// Part of the level music, faded by intensity
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum MusicLayer {
+    Track,
+    Drums,
+    BattleDrums,
+}
+
+impl MusicLayer {
+    const ALL: [MusicLayer; 3] = [
+        MusicLayer::Track,
+        MusicLayer::Drums,
+        MusicLayer::BattleDrums,
+    ];
+
+    fn volume(&self, intensity: f32) -> f32 {
+        let fade_in = |from: f32, to: f32| ((intensity - from) / (to - from)).clamp(0., 1.);
+        match self {
+            MusicLayer::Track => 1. - 0.4 * intensity,
+            MusicLayer::Drums => 0.6 * fade_in(0.15, 0.5),
+            MusicLayer::BattleDrums => 0.5 * fade_in(0.55, 0.9),
+        }
+    }
+}
+
+/// Smoothed threat level driving the music layers
+#[derive(Resource, Default)]
+struct MusicDirector {
+    intensity: f32,
+}
+
+fn setup_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut drum_loops: ResMut<Assets<DrumLoop>>,
+    mut stingers: ResMut<Assets<Stinger>>,
+) {
+    commands.insert_resource(MusicAssets {
+        level: asset_server.load("audio/565_tocf_mono_level_1.ogg"),
+        // Kick on the beat
+        drums: drum_loops.add(DrumLoop {
+            pattern: 0b0001_0001_0001_0001,
+            pitch: 55.,
+            decay: 8.,
+        }),
+        // Rolling toms on the offbeats
+        battle_drums: drum_loops.add(DrumLoop {
+            pattern: 0b1110_1110_1110_1010,
+            pitch: 110.,
+            decay: 14.,
+        }),
+        // Rising fourths call the next wave in
+        wave_stinger: stingers.add(Stinger {
+            notes: &[196.0, 261.6, 349.2],
+            note_length: 0.15,
+        }),
+        // Falling minor triad
+        game_over_stinger: stingers.add(Stinger {
+            notes: &[392.0, 311.1, 261.6, 196.0],
+            note_length: 0.35,
+        }),
+    });
+}
+
+fn reset_music_director(mut director: ResMut<MusicDirector>) {
+    *director = MusicDirector::default();
+}
+
+/// Start the level music layers, unless they are playing already, as on resuming from pause
+fn start_level_music(
+    mut commands: Commands,
+    music_assets: Res<MusicAssets>,
+    mixer: Res<Mixer>,
+    director: Res<MusicDirector>,
+    layer_query: Query<(), With<MusicLayer>>,
+) {
+    if !layer_query.is_empty() {
+        return;
+    }
+    for layer in MusicLayer::ALL {
+        let volume = layer.volume(director.intensity);
+        let mut entity = commands.spawn((MixerChannel::new(Channel::Music, volume), layer));
+        match layer {
+            MusicLayer::Track => entity.insert(AudioBundle {
+                source: music_assets.level.clone(),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Loop,
+                    volume: mixer.volume(Channel::Music, volume),
+                    ..default()
+                },
+            }),
+            MusicLayer::Drums | MusicLayer::BattleDrums => entity.insert(AudioSourceBundle {
+                source: if layer == MusicLayer::Drums {
+                    music_assets.drums.clone()
+                } else {
+                    music_assets.battle_drums.clone()
+                },
+                settings: PlaybackSettings {
+                    // Drum loops never end on their own
+                    mode: PlaybackMode::Once,
+                    volume: mixer.volume(Channel::Music, volume),
+                    ..default()
+                },
+            }),
+        };
+    }
+}
+
+fn stop_level_music(
+    mut commands: Commands,
+    layer_query: Query<(Entity, Option<&AudioSink>), With<MusicLayer>>,
+) {
+    for (entity, audio_sink) in layer_query.iter() {
+        if let Some(audio_sink) = audio_sink {
+            audio_sink.stop();
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Aircraft and bombers alike
+type AnyAircraft = Or<(With<Aircraft>, With<Bomber>)>;
+
+/// Follow the threat on screen: landed troopers, falling bombs and aircraft overhead
+fn direct_music(
+    time: Res<Time>,
+    mut director: ResMut<MusicDirector>,
+    paratrooper_query: Query<&Paratrooper>,
+    bomb_query: Query<(), With<Bomb>>,
+    aircraft_query: Query<(), AnyAircraft>,
+    mut layer_query: Query<(&MusicLayer, &mut MixerChannel)>,
+) {
+    let landed = paratrooper_query
+        .iter()
+        .filter(|paratrooper| {
+            matches!(
+                paratrooper.state,
+                ParatrooperState::Landed | ParatrooperState::Assault
+            )
+        })
+        .count();
+    let threat = (landed as f32 * LANDED_THREAT
+        + bomb_query.iter().count() as f32 * BOMB_THREAT
+        + aircraft_query.iter().count() as f32 * AIRCRAFT_THREAT)
+        .min(1.);
+
+    let rate = if threat > director.intensity {
+        INTENSITY_RISE
+    } else {
+        INTENSITY_FALL
+    };
+    let step = rate * time.delta_seconds();
+    director.intensity += (threat - director.intensity).clamp(-step, step);
+
+    for (layer, mut mixer_channel) in layer_query.iter_mut() {
+        let volume = layer.volume(director.intensity);
+        if (mixer_channel.volume - volume).abs() > 0.001 {
+            mixer_channel.volume = volume;
+        }
+    }
+}
+
+fn play_stinger(commands: &mut Commands, mixer: &Mixer, stinger: &Handle<Stinger>) {
+    commands.spawn((
+        AudioSourceBundle {
+            source: stinger.clone(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: mixer.volume(Channel::Music, 1.0),
+                ..default()
+            },
+        },
+        MixerChannel::new(Channel::Music, 1.0),
+    ));
+}
+
+fn wave_stinger(
+    mut commands: Commands,
+    music_assets: Res<MusicAssets>,
+    mixer: Res<Mixer>,
+    mut event_reader: EventReader<WaveStartEvent>,
+) {
+    if event_reader.read().last().is_some() {
+        play_stinger(&mut commands, &mixer, &music_assets.wave_stinger);
+    }
+}
+
+fn game_over_stinger(mut commands: Commands, music_assets: Res<MusicAssets>, mixer: Res<Mixer>) {
+    play_stinger(&mut commands, &mixer, &music_assets.game_over_stinger);
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<DrumLoop>()
+            .add_audio_source::<Stinger>()
+            .init_resource::<MusicDirector>()
+            .add_systems(Startup, setup_music)
+            .add_systems(OnEnter(AppState::NewGame), reset_music_director)
+            .add_systems(OnEnter(AppState::InGame), start_level_music)
+            .add_systems(
+                Update,
+                (direct_music, wave_stinger).run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                (stop_level_music, game_over_stinger),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), stop_level_music);
+    }
+}
@@ -1,9 +1,14 @@
-use crate::aircraft::Aircraft;
+use crate::aircraft::{Aircraft, DropZone};
+use crate::player::Player;
+use crate::scenario::Scenarios;
+use crate::session::GameSession;
 use crate::terrain::Ground;
+use crate::weather::Weather;
 use crate::{AppState, BulletCollisionEvent, CollisionType, GibEvent, LandingEvent};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 const PARATROOPER_SPAWN_PROBABILITY: f32 = 0.007;
 const PARACHUTE_SPAWN_PROBABILITY: f32 = 0.01;
@@ -26,12 +31,15 @@ const PARATROOPER_Z: f32 = 2.0;
 pub struct Paratrooper {
     pub state: ParatrooperState,
     pub has_deployed_chute: bool,
+    /// Decides when this trooper's chute opens
+    chute_rng: StdRng,
 }
 impl Default for Paratrooper {
     fn default() -> Paratrooper {
         Paratrooper {
             state: ParatrooperState::Falling,
             has_deployed_chute: false,
+            chute_rng: StdRng::from_entropy(),
         }
     }
 }
@@ -39,20 +47,37 @@ impl Default for Paratrooper {
 #[derive(Component)]
 pub struct Parachute;
 
-#[derive(PartialEq)]
+/// Player who shot away this paratrooper's parachute, credited if they crash
+#[derive(Component)]
+pub struct ShotDownBy(pub Player);
+
+#[derive(PartialEq, Debug)]
 pub enum ParatrooperState {
     Falling,
     Floating,
     Landed,
     Assault,
 }
+/// Decides when troopers jump, and seeds each one's chute. Seeded from the scenario when one is
+/// playing, so a scenario drops the same way every run. Chutes draw from their own trooper, so
+/// how long the others take to land never shifts the jumps.
+#[derive(Resource)]
+struct DropRng(StdRng);
+
+impl Default for DropRng {
+    fn default() -> DropRng {
+        DropRng(StdRng::from_entropy())
+    }
+}
+
 #[derive(Resource)]
 struct ParatrooperTextures {
     pub body_handle: Handle<Image>,      // 31 x 49
     pub parachute_handle: Handle<Image>, // 89 x 86
 }
 
-/// Load paratrooper textures
+/// Load paratrooper textures. There is one body drawing for every state, so the body is not
+/// animated. Falling, floating, landed and walking poses are out of scope until they are drawn.
 fn setup_paratroopers(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(ParatrooperTextures {
         body_handle: asset_server.load("images/paratrooperfly1_body.png"),
@@ -83,6 +108,13 @@ fn setup_paratroopers(mut commands: Commands, asset_server: Res<AssetServer>) {
 //    }
 //}
 
+fn seed_drops(mut drop_rng: ResMut<DropRng>, scenarios: Res<Scenarios>) {
+    *drop_rng = match scenarios.selected() {
+        Some(scenario) => DropRng(StdRng::seed_from_u64(scenario.seed)),
+        None => DropRng::default(),
+    };
+}
+
 fn paratrooper_sprite_bundle(paratrooper_textures: &Res<ParatrooperTextures>) -> SpriteBundle {
     SpriteBundle {
         texture: paratrooper_textures.body_handle.clone(),
@@ -94,19 +126,26 @@ fn paratrooper_sprite_bundle(paratrooper_textures: &Res<ParatrooperTextures>) ->
 fn spawn_paratroopers(
     mut commands: Commands,
     paratrooper_textures: Res<ParatrooperTextures>,
-    mut query: Query<(&mut Aircraft, &Transform, &Velocity)>,
+    mut drop_rng: ResMut<DropRng>,
+    mut query: Query<(&mut Aircraft, &Transform, &Velocity, Option<&mut DropZone>)>,
 ) {
-    let mut rng = rand::thread_rng();
-    for (mut aircraft, transform, velocity) in query.iter_mut() {
+    for (mut aircraft, transform, velocity, drop_zone) in query.iter_mut() {
         let pos_x = transform.translation.x.abs();
-        if aircraft.paratroopers > 0
-            && pos_x < PARATROOPER_SPAWN_X_MAX
-            && pos_x > PARATROOPER_SPAWN_X_MIN
-            && rng.gen_range(0.0..1.0) < PARATROOPER_SPAWN_PROBABILITY
-        {
+        let heading = velocity.linvel.x.signum();
+        let should_jump = match drop_zone.as_deref() {
+            Some(drop_zone) => drop_zone.is_over(transform.translation.x, heading),
+            None => {
+                pos_x < PARATROOPER_SPAWN_X_MAX
+                    && pos_x > PARATROOPER_SPAWN_X_MIN
+                    && drop_rng.0.gen_range(0.0..1.0) < PARATROOPER_SPAWN_PROBABILITY
+            }
+        };
+        if aircraft.paratroopers > 0 && should_jump {
             aircraft.paratroopers -= 1;
+            if let Some(mut drop_zone) = drop_zone {
+                drop_zone.dropped += 1;
+            }
             // Offset to back of plane
-            let heading = velocity.linvel.x.signum();
             let paratrooper_pos = Vec2::new(
                 transform.translation.x - heading * 35.,
                 transform.translation.y - 25.,
@@ -146,24 +185,31 @@ fn spawn_paratroopers(
                     Group::GROUP_2 | Group::GROUP_3 | Group::GROUP_4,
                 ))
                 .insert(ActiveEvents::COLLISION_EVENTS)
-                .insert(Paratrooper::default());
+                .insert(Paratrooper {
+                    chute_rng: StdRng::seed_from_u64(drop_rng.0.gen()),
+                    ..default()
+                })
+                .insert(GameSession);
         }
     }
 }
 
+/// A paratrooper along with its chute, if open
+type ShotParatrooper<'a> = (
+    Entity,
+    &'a mut Paratrooper,
+    &'a Transform,
+    &'a mut Damping,
+    &'a mut GravityScale,
+    &'a mut Velocity,
+    Option<&'a Children>,
+);
+
 /// Handle bullet <-> parachute/trooper collisions
 fn bullet_collision_system(
     mut commands: Commands,
     parachute_query: Query<(Entity, &Transform), With<Parachute>>,
-    mut paratrooper_query: Query<(
-        Entity,
-        &mut Paratrooper,
-        &Transform,
-        &mut Damping,
-        &mut GravityScale,
-        &mut Velocity,
-        Option<&Children>,
-    )>,
+    mut paratrooper_query: Query<ShotParatrooper>,
     mut event_reader: EventReader<BulletCollisionEvent>,
     mut event_writer: EventWriter<GibEvent>,
 ) {
@@ -186,6 +232,7 @@ fn bullet_collision_system(
                             PARATROOPER_SCALE,
                             1.,
                         )),
+                        player: Some(event.player),
                     });
                     commands.entity(paratrooper_entity).despawn_recursive();
                 }
@@ -195,7 +242,7 @@ fn bullet_collision_system(
                 {
                     // Reset falling physics
                     for (
-                        _paratrooper_entity,
+                        paratrooper_entity,
                         mut paratrooper,
                         _transform,
                         mut damping,
@@ -213,6 +260,9 @@ fn bullet_collision_system(
                                     velocity.linvel.y =
                                         (1.5 * MIN_PARACHUTE_VELOCITY).min(velocity.linvel.y);
                                     paratrooper.state = ParatrooperState::Falling;
+                                    commands
+                                        .entity(paratrooper_entity)
+                                        .try_insert(ShotDownBy(event.player));
                                 }
                             }
                         }
@@ -226,26 +276,37 @@ fn bullet_collision_system(
     }
 }
 
+/// A paratrooper along with its chute, and who shot the chute away
+type LandingParatrooper<'a> = (
+    Entity,
+    &'a mut Paratrooper,
+    &'a Transform,
+    &'a mut Velocity,
+    Option<&'a Children>,
+    Option<&'a ShotDownBy>,
+);
+
 /// Detect paratrooper landings
 fn paratrooper_landing_system(
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
-    mut paratrooper_query: Query<(
-        Entity,
-        &mut Paratrooper,
-        &Transform,
-        &mut Velocity,
-        Option<&Children>,
-    )>,
+    mut paratrooper_query: Query<LandingParatrooper>,
     ground_query: Query<Entity, With<Ground>>,
     mut event_writer: EventWriter<LandingEvent>,
     mut gib_event_writer: EventWriter<GibEvent>,
 ) {
     for collision_event in collision_events.read() {
         for ground_entity in ground_query.iter() {
-            for (paratrooper_entity, mut paratrooper, &transform, mut _velocity, children_option) in
-                paratrooper_query.iter_mut()
+            for (
+                paratrooper_entity,
+                mut paratrooper,
+                &transform,
+                mut velocity,
+                children_option,
+                shot_down_by,
+            ) in paratrooper_query.iter_mut()
             {
+                let player = shot_down_by.map(|shot_down_by| shot_down_by.0);
                 if let &CollisionEvent::Started(entity1, entity2, _) = collision_event {
                     // Ground / Paratrooper contact
                     if (paratrooper_entity == entity1 && ground_entity == entity2)
@@ -259,12 +320,15 @@ fn paratrooper_landing_system(
                                     PARATROOPER_SCALE,
                                     1.0,
                                 )),
+                                player,
                             });
                             commands.entity(paratrooper_entity).despawn_recursive();
                         }
 
                         if paratrooper.state != ParatrooperState::Landed {
                             paratrooper.state = ParatrooperState::Landed;
+                            // Stop any drift from the wind
+                            velocity.linvel.x = 0.;
                             event_writer.send(LandingEvent(paratrooper_entity));
                         }
 
@@ -281,6 +345,7 @@ fn paratrooper_landing_system(
                                     PARATROOPER_SCALE,
                                     1.0,
                                 )),
+                                player,
                             });
                             commands.entity(paratrooper_entity).despawn_recursive();
                         }
@@ -291,15 +356,10 @@ fn paratrooper_landing_system(
     }
 }
 
-fn despawn_paratrooper_system(mut commands: Commands, query: Query<Entity, With<Paratrooper>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
 fn spawn_parachutes(
     mut commands: Commands,
     textures: Res<ParatrooperTextures>,
+    weather: Res<Weather>,
     mut paratrooper_query: Query<(
         Entity,
         &mut Paratrooper,
@@ -308,13 +368,12 @@ fn spawn_parachutes(
         &mut GravityScale,
     )>,
 ) {
-    let mut rng = rand::thread_rng();
     for (paratrooper_entity, mut paratrooper, mut velocity, mut damping, mut gravity) in
         paratrooper_query.iter_mut()
     {
         if !paratrooper.has_deployed_chute
             && paratrooper.state == ParatrooperState::Falling
-            && rng.gen_range(0.0..1.0) < PARACHUTE_SPAWN_PROBABILITY
+            && paratrooper.chute_rng.gen_range(0.0..1.0) < PARACHUTE_SPAWN_PROBABILITY
         {
             paratrooper.has_deployed_chute = true;
             paratrooper.state = ParatrooperState::Floating;
@@ -342,14 +401,15 @@ fn spawn_parachutes(
                 .entity(paratrooper_entity)
                 .push_children(&[parachute_entity]);
 
-            // Add air resistance drag
-            damping.linear_damping = PARACHUTE_DAMPING;
+            // Add air resistance drag, more or less depending on the weather
+            let weather = weather.data();
+            damping.linear_damping = PARACHUTE_DAMPING * weather.parachute_damping;
 
             // Cap y velocity
             velocity.linvel.y = velocity.linvel.y.max(MIN_PARACHUTE_VELOCITY);
 
             // Reduce gravity
-            gravity.0 = PARACHUTE_GRAVITY_SCALE;
+            gravity.0 = PARACHUTE_GRAVITY_SCALE * weather.parachute_gravity;
         }
     }
 }
@@ -358,7 +418,9 @@ pub struct ParatrooperPlugin;
 
 impl Plugin for ParatrooperPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_paratroopers)
+        app.init_resource::<DropRng>()
+            .add_systems(Startup, setup_paratroopers)
+            .add_systems(OnEnter(AppState::NewGame), seed_drops)
             .add_systems(
                 Update,
                 (
@@ -368,8 +430,6 @@ impl Plugin for ParatrooperPlugin {
                     spawn_parachutes,
                 )
                     .run_if(in_state(AppState::InGame)),
-            )
-            .add_systems(OnEnter(AppState::MainMenu), despawn_paratrooper_system)
-            .add_systems(OnEnter(AppState::GameOver), despawn_paratrooper_system);
+            );
     }
 }
@@ -0,0 +1,367 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::utils::HashMap;
+use rand::Rng;
+use serde::Deserialize;
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::fs;
+use std::ops::Range;
+
+use crate::menu::game_time_running;
+use crate::session::GameSession;
+use crate::{ExplosionEvent, ExplosionType, GunshotEvent, LandingEvent};
+
+const PARTICLES_PATH: &str = "assets/particles.ron";
+
+/// Particles draw in front of the planes and behind the clouds
+const PARTICLE_Z: f32 = 5.;
+/// Distance from the gun's pivot to the muzzle
+const MUZZLE_DISTANCE: f32 = 30.;
+const SOFT_TEXTURE_SIZE: usize = 32;
+/// Width of a streak, which is as long as the particle's size
+const STREAK_WIDTH: f32 = 1.5;
+
+/// Particle look. Soft puffs suit smoke and dust, hard squares suit debris, and streaks point
+/// the way they were thrown.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum Shape {
+    Soft,
+    Square,
+    Streak,
+}
+
+impl Shape {
+    fn sprite_size(&self, size: f32) -> Vec2 {
+        match self {
+            Shape::Soft | Shape::Square => Vec2::splat(size),
+            Shape::Streak => Vec2::new(STREAK_WIDTH, size),
+        }
+    }
+}
+
+/// Everything there is to know about emitting an effect, as read from `assets/particles.ron`
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmitterConfig {
+    /// Particles per burst
+    burst: usize,
+    /// Particles per second from an attached emitter
+    rate: f32,
+    /// Seconds a particle lives
+    lifetime: Range<f32>,
+    speed: Range<f32>,
+    /// Full width of the cone particles leave in, in radians, centered on the emit direction
+    spread: f32,
+    /// Size in pixels at birth and at death
+    size: (f32, f32),
+    color: (Color, Color),
+    /// Vertical acceleration, in pixels per second squared
+    gravity: f32,
+    /// Fraction of speed lost per second
+    drag: f32,
+    shape: Shape,
+    /// Effect each particle trails, if any, and the chance a particle gets one
+    #[serde(default)]
+    trail: Option<(Effect, f64)>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Effect {
+    /// Trails behind wrecks of shot down planes, and some debris
+    Smoke,
+    Debris,
+    MuzzleFlash,
+    ShellCasing,
+    Dust,
+    Rain,
+    Snow,
+}
+
+impl Effect {
+    const ALL: [Effect; 7] = [
+        Effect::Smoke,
+        Effect::Debris,
+        Effect::MuzzleFlash,
+        Effect::ShellCasing,
+        Effect::Dust,
+        Effect::Rain,
+        Effect::Snow,
+    ];
+}
+
+/// Read every effect's emitter. Particles are part of the look of the game, so a missing or
+/// broken file is fatal.
+fn load_effects() -> HashMap<Effect, EmitterConfig> {
+    let effects: HashMap<Effect, EmitterConfig> = fs::read_to_string(PARTICLES_PATH)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| ron::from_str(&contents).map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| panic!("Could not load {}: {}", PARTICLES_PATH, error));
+    if let Some(effect) = Effect::ALL
+        .into_iter()
+        .find(|effect| !effects.contains_key(effect))
+    {
+        panic!("{} has no {:?} effect", PARTICLES_PATH, effect);
+    }
+    effects
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    effect: Effect,
+}
+
+/// Continuously emits an effect from the entity it is attached to
+#[derive(Component)]
+pub struct ParticleEmitter {
+    effect: Effect,
+    /// Particles owed, carried between frames
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: Effect) -> ParticleEmitter {
+        ParticleEmitter {
+            effect,
+            pending: 0.,
+        }
+    }
+}
+
+/// Emitter settings for every effect, and the texture soft particles share
+#[derive(Resource)]
+pub struct ParticleLibrary {
+    effects: HashMap<Effect, EmitterConfig>,
+    soft: Handle<Image>,
+}
+
+impl ParticleLibrary {
+    fn config(&self, effect: Effect) -> &EmitterConfig {
+        &self.effects[&effect]
+    }
+}
+
+/// Round puff fading out to the edge
+fn soft_image() -> Image {
+    let size = SOFT_TEXTURE_SIZE;
+    let mut data = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let center = size as f32 / 2.;
+            let distance = Vec2::new(x as f32 + 0.5 - center, y as f32 + 0.5 - center).length();
+            let alpha = (1. - distance / center).clamp(0., 1.);
+            data.extend_from_slice(&[255, 255, 255, (alpha * alpha * 255.) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: size as u32,
+            height: size as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn setup_particles(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    commands.insert_resource(ParticleLibrary {
+        effects: load_effects(),
+        soft: images.add(soft_image()),
+    });
+}
+
+fn spawn_particle(
+    commands: &mut Commands,
+    particles: &ParticleLibrary,
+    effect: Effect,
+    position: Vec2,
+    direction: f32,
+) {
+    let config = particles.config(effect);
+    let mut rng = rand::thread_rng();
+    let angle = direction + rng.gen_range(-0.5..=0.5) * config.spread;
+    let speed = rng.gen_range(config.speed.clone());
+    let texture = match config.shape {
+        Shape::Soft => particles.soft.clone(),
+        Shape::Square | Shape::Streak => Handle::default(),
+    };
+    let rotation = match config.shape {
+        Shape::Streak => Quat::from_rotation_z(angle - FRAC_PI_2),
+        Shape::Soft | Shape::Square => Quat::IDENTITY,
+    };
+    let mut particle = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: config.color.0,
+                custom_size: Some(config.shape.sprite_size(config.size.0)),
+                ..default()
+            },
+            texture,
+            transform: Transform::from_translation(position.extend(PARTICLE_Z))
+                .with_rotation(rotation),
+            ..default()
+        },
+        Particle {
+            velocity: Vec2::from_angle(angle) * speed,
+            age: 0.,
+            lifetime: rng.gen_range(config.lifetime.clone()),
+            effect,
+        },
+        GameSession,
+    ));
+    if let Some((trail, chance)) = config.trail {
+        if rng.gen_bool(chance) {
+            particle.insert(ParticleEmitter::new(trail));
+        }
+    }
+}
+
+/// Emit one burst of `effect` at `position`, aimed along `direction` in radians
+pub fn spawn_burst(
+    commands: &mut Commands,
+    particles: &ParticleLibrary,
+    effect: Effect,
+    position: Vec2,
+    direction: f32,
+) {
+    for _ in 0..particles.config(effect).burst {
+        spawn_particle(commands, particles, effect, position, direction);
+    }
+}
+
+fn emit_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    particles: Res<ParticleLibrary>,
+    mut query: Query<(&mut ParticleEmitter, &GlobalTransform)>,
+) {
+    for (mut emitter, transform) in query.iter_mut() {
+        emitter.pending += particles.config(emitter.effect).rate * time.delta_seconds();
+        while emitter.pending >= 1. {
+            emitter.pending -= 1.;
+            let position = transform.translation().truncate();
+            spawn_particle(
+                &mut commands,
+                &particles,
+                emitter.effect,
+                position,
+                FRAC_PI_2,
+            );
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    particles: Res<ParticleLibrary>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in query.iter_mut() {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let config = particles.config(particle.effect);
+        particle.velocity.y += config.gravity * dt;
+        particle.velocity *= (1. - config.drag * dt).max(0.);
+        transform.translation += (particle.velocity * dt).extend(0.);
+
+        let t = particle.age / particle.lifetime;
+        let size = config.size.0 + (config.size.1 - config.size.0) * t;
+        sprite.custom_size = Some(config.shape.sprite_size(size));
+        let (from, to) = (config.color.0.as_rgba_f32(), config.color.1.as_rgba_f32());
+        let mix = |i: usize| from[i] + (to[i] - from[i]) * t;
+        sprite.color = Color::rgba(mix(0), mix(1), mix(2), mix(3));
+    }
+}
+
+/// Debris flies from aircraft explosions, trailing smoke
+fn explosion_particles(
+    mut commands: Commands,
+    particles: Res<ParticleLibrary>,
+    mut event_reader: EventReader<ExplosionEvent>,
+) {
+    for event in event_reader
+        .read()
+        .filter(|event| event.explosion_type == ExplosionType::Aircraft)
+    {
+        let position = event.transform.translation.truncate();
+        spawn_burst(
+            &mut commands,
+            &particles,
+            Effect::Debris,
+            position,
+            FRAC_PI_2,
+        );
+    }
+}
+
+/// Flash from the muzzle and a casing thrown off to the side
+fn gunshot_particles(
+    mut commands: Commands,
+    particles: Res<ParticleLibrary>,
+    mut event_reader: EventReader<GunshotEvent>,
+) {
+    for event in event_reader.read() {
+        let barrel = event.direction.y.atan2(event.direction.x);
+        let muzzle = event.translation.truncate() + event.direction * MUZZLE_DISTANCE;
+        spawn_burst(
+            &mut commands,
+            &particles,
+            Effect::MuzzleFlash,
+            muzzle,
+            barrel,
+        );
+        // Ejected to the right of the barrel, and up
+        let ejection = barrel - FRAC_PI_2 + PI / 3.;
+        let breech = event.translation.truncate();
+        spawn_burst(
+            &mut commands,
+            &particles,
+            Effect::ShellCasing,
+            breech,
+            ejection,
+        );
+    }
+}
+
+/// Kick up dust where a paratrooper touches down
+fn landing_particles(
+    mut commands: Commands,
+    particles: Res<ParticleLibrary>,
+    mut event_reader: EventReader<LandingEvent>,
+    transform_query: Query<&Transform>,
+) {
+    for event in event_reader.read() {
+        if let Ok(transform) = transform_query.get(event.0) {
+            let position = transform.translation.truncate();
+            spawn_burst(&mut commands, &particles, Effect::Dust, position, FRAC_PI_2);
+        }
+    }
+}
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_particles).add_systems(
+            Update,
+            (
+                explosion_particles,
+                gunshot_particles,
+                landing_particles,
+                emit_system,
+                update_particles,
+            )
+                .run_if(game_time_running),
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::input::BindingSlot;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Owner of a gun assembly, its bullets and the score they earn. Player one is `Player(0)`.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Player(pub usize);
+
+/// How player one aims: rotation keys, or turning the barrel toward the mouse cursor
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ControlScheme {
+    #[default]
+    Keys,
+    Mouse,
+}
+
+/// Which gamepads drive a gun
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GamepadSelection {
+    /// Every connected gamepad
+    Any,
+    /// The nth connected gamepad, ordered by id
+    Nth(usize),
+    /// Keyboard only
+    None,
+}
+
+impl GamepadSelection {
+    /// Connected gamepads matching this selection
+    pub fn gamepads(&self, gamepads: &Gamepads) -> Vec<Gamepad> {
+        let mut connected: Vec<Gamepad> = gamepads.iter().collect();
+        connected.sort_by_key(|gamepad| gamepad.id);
+        match self {
+            GamepadSelection::Any => connected,
+            GamepadSelection::Nth(n) => connected.into_iter().skip(*n).take(1).collect(),
+            GamepadSelection::None => Vec::new(),
+        }
+    }
+}
+
+/// Which bindings and gamepads drive one gun
+#[derive(Component, Clone, Debug)]
+pub struct PlayerControls {
+    pub bindings: BindingSlot,
+    pub gamepad: GamepadSelection,
+    /// Aim at the mouse cursor and fire with the left mouse button
+    pub mouse: bool,
+}
+
+impl PlayerControls {
+    /// One player on the whole keyboard and any gamepad
+    pub fn single_player() -> PlayerControls {
+        PlayerControls {
+            bindings: BindingSlot::Solo,
+            gamepad: GamepadSelection::Any,
+            mouse: false,
+        }
+    }
+
+    /// The whole keyboard, leaving gamepads to another player
+    pub fn keyboard_only() -> PlayerControls {
+        PlayerControls {
+            gamepad: GamepadSelection::None,
+            ..PlayerControls::single_player()
+        }
+    }
+
+    /// Player one's keys and the first gamepad
+    pub fn player_one() -> PlayerControls {
+        PlayerControls {
+            bindings: BindingSlot::PlayerOne,
+            gamepad: GamepadSelection::Nth(0),
+            mouse: false,
+        }
+    }
+
+    /// Player two's keys and the second gamepad
+    pub fn player_two() -> PlayerControls {
+        PlayerControls {
+            bindings: BindingSlot::PlayerTwo,
+            gamepad: GamepadSelection::Nth(1),
+            mouse: false,
+        }
+    }
+
+    pub fn with_control_scheme(self, control_scheme: ControlScheme) -> PlayerControls {
+        PlayerControls {
+            mouse: control_scheme == ControlScheme::Mouse,
+            ..self
+        }
+    }
+}
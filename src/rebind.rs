@@ -0,0 +1,302 @@
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::input::{button_name, key_name, Action, ActionMap, BindingSlot, InputBindings};
+use crate::menu::{AppState, FontHandles};
+
+const FONT_SIZE: f32 = 28.0;
+const TEXT_COLOR: Color = Color::GOLD;
+const SELECTED_COLOR: Color = Color::RED;
+/// Fixed, so the screen can always be worked whatever is bound
+const CLEAR_KEYS: [KeyCode; 2] = [KeyCode::Backspace, KeyCode::Delete];
+const CLEAR_BUTTON: GamepadButtonType = GamepadButtonType::West;
+const BACK_KEY: KeyCode = KeyCode::Escape;
+const BACK_BUTTON: GamepadButtonType = GamepadButtonType::East;
+
+#[derive(Component)]
+struct BindingsScreen;
+
+#[derive(Component)]
+struct BindingsText;
+
+/// Selected slot and action on the bindings screen
+#[derive(Resource, Default)]
+struct RebindCursor {
+    slot: usize,
+    row: usize,
+    /// Waiting for the key or button to bind to the selected action
+    capturing: bool,
+}
+
+impl RebindCursor {
+    fn slot(&self) -> BindingSlot {
+        BindingSlot::ALL[self.slot]
+    }
+
+    fn action(&self) -> Action {
+        self.slot().actions()[self.row]
+    }
+}
+
+fn spawn_bindings_screen(mut commands: Commands, font_handles: Res<FontHandles>) {
+    commands.insert_resource(RebindCursor::default());
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(100.0),
+                top: Val::Px(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(BindingsScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "CONTROLS",
+                TextStyle {
+                    font: font_handles.handle.clone(),
+                    font_size: 75.0,
+                    color: Color::RED,
+                },
+            ));
+            parent
+                .spawn(TextBundle::from_sections([]))
+                .insert(BindingsText);
+        });
+}
+
+fn despawn_bindings_screen(mut commands: Commands, query: Query<Entity, With<BindingsScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<RebindCursor>();
+}
+
+/// Add `input` to the bindings of `action`, keeping the ones it has. Another action in the same
+/// slot that had `input` loses it, so no two actions share a key or button.
+fn bind<T: Copy + PartialEq>(map: &mut BTreeMap<Action, Vec<T>>, action: Action, input: T) {
+    for (_, inputs) in map.iter_mut().filter(|(&other, _)| other != action) {
+        inputs.retain(|&bound| bound != input);
+    }
+    let inputs = map.entry(action).or_default();
+    if !inputs.contains(&input) {
+        inputs.push(input);
+    }
+}
+
+/// Menus can't be left without a way to pause or confirm, so a menu action with nothing bound
+/// goes back to its defaults
+fn restore_menu_defaults(menu: &mut ActionMap) {
+    let defaults = InputBindings::default().menu;
+    for &action in BindingSlot::Menu.actions() {
+        if menu.keys(action).is_empty() && menu.buttons(action).is_empty() {
+            menu.keys.insert(action, defaults.keys(action).to_vec());
+            menu.buttons
+                .insert(action, defaults.buttons(action).to_vec());
+        }
+    }
+}
+
+/// Arrow keys or D-pad move the cursor, Confirm waits for a key or button to add, the clear
+/// keys or button clear the action, or reset a menu action, and the back key or button goes back
+/// to the settings screen.
+fn bindings_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    button_inputs: Res<ButtonInput<GamepadButton>>,
+    mut cursor: ResMut<RebindCursor>,
+    mut bindings: ResMut<InputBindings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let slot = cursor.slot();
+    let action = cursor.action();
+
+    if cursor.capturing {
+        if keyboard_input.just_pressed(BACK_KEY) {
+            cursor.capturing = false;
+        } else if let Some(&key) = keyboard_input.get_just_pressed().next() {
+            bind(&mut bindings.map_mut(slot).keys, action, key);
+            restore_menu_defaults(&mut bindings.menu);
+            cursor.capturing = false;
+        } else if let Some(button) = button_inputs.get_just_pressed().next() {
+            bind(
+                &mut bindings.map_mut(slot).buttons,
+                action,
+                button.button_type,
+            );
+            restore_menu_defaults(&mut bindings.menu);
+            cursor.capturing = false;
+        }
+        return;
+    }
+
+    let gamepad_pressed = |button_type: GamepadButtonType| {
+        button_inputs
+            .get_just_pressed()
+            .any(|button| button.button_type == button_type)
+    };
+    let up =
+        keyboard_input.just_pressed(KeyCode::ArrowUp) || gamepad_pressed(GamepadButtonType::DPadUp);
+    let down = keyboard_input.just_pressed(KeyCode::ArrowDown)
+        || gamepad_pressed(GamepadButtonType::DPadDown);
+    let left = keyboard_input.just_pressed(KeyCode::ArrowLeft)
+        || gamepad_pressed(GamepadButtonType::DPadLeft);
+    let right = keyboard_input.just_pressed(KeyCode::ArrowRight)
+        || gamepad_pressed(GamepadButtonType::DPadRight);
+    let confirm = keyboard_input
+        .any_just_pressed(bindings.menu.keys(Action::Confirm).iter().copied())
+        || bindings
+            .menu
+            .buttons(Action::Confirm)
+            .iter()
+            .any(|&button_type| gamepad_pressed(button_type));
+    let clear = keyboard_input.any_just_pressed(CLEAR_KEYS) || gamepad_pressed(CLEAR_BUTTON);
+    let back = keyboard_input.just_pressed(BACK_KEY) || gamepad_pressed(BACK_BUTTON);
+
+    let rows = slot.actions().len();
+    if up {
+        cursor.row = (cursor.row + rows - 1) % rows;
+    }
+    if down {
+        cursor.row = (cursor.row + 1) % rows;
+    }
+    if left || right {
+        let slots = BindingSlot::ALL.len();
+        cursor.slot = if left {
+            (cursor.slot + slots - 1) % slots
+        } else {
+            (cursor.slot + 1) % slots
+        };
+        cursor.row = cursor.row.min(cursor.slot().actions().len() - 1);
+    }
+    if confirm {
+        cursor.capturing = true;
+    } else if clear {
+        let map = bindings.map_mut(slot);
+        map.keys.remove(&action);
+        map.buttons.remove(&action);
+        restore_menu_defaults(&mut bindings.menu);
+    } else if back {
+        next_state.set(AppState::Settings);
+    }
+}
+
+fn update_bindings_text(
+    cursor: Res<RebindCursor>,
+    bindings: Res<InputBindings>,
+    font_handles: Res<FontHandles>,
+    mut query: Query<&mut Text, With<BindingsText>>,
+) {
+    let style = |color: Color| TextStyle {
+        font: font_handles.handle.clone(),
+        font_size: FONT_SIZE,
+        color,
+    };
+    let slot = cursor.slot();
+    let map = bindings.map(slot);
+
+    let mut sections = vec![TextSection::new(
+        format!("<  {}  >\n\n", slot.name()),
+        style(Color::WHITE),
+    )];
+    for (row, &action) in slot.actions().iter().enumerate() {
+        let keys: Vec<String> = map.keys(action).iter().map(|&key| key_name(key)).collect();
+        let buttons: Vec<String> = map
+            .buttons(action)
+            .iter()
+            .map(|&button_type| button_name(button_type))
+            .collect();
+        let selected = row == cursor.row;
+        sections.push(TextSection::new(
+            format!(
+                "{}{}:  {}    gamepad: {}\n",
+                if selected { "> " } else { "   " },
+                action.name(),
+                keys.join(", "),
+                buttons.join(", "),
+            ),
+            style(if selected { SELECTED_COLOR } else { TEXT_COLOR }),
+        ));
+    }
+    let help = if cursor.capturing {
+        format!(
+            "\nPress a key or gamepad button to add to {}. {} cancels.",
+            cursor.action().name(),
+            key_name(BACK_KEY)
+        )
+    } else {
+        let clear_keys: Vec<String> = CLEAR_KEYS.iter().map(|&key| key_name(key)).collect();
+        format!(
+            "\nUp/Down: select    Left/Right: player    {}: add\n{}/{}: {}    {}/{}: back",
+            bindings.menu.describe(Action::Confirm),
+            clear_keys.join("/"),
+            button_name(CLEAR_BUTTON),
+            if slot == BindingSlot::Menu {
+                "reset"
+            } else {
+                "clear"
+            },
+            key_name(BACK_KEY),
+            button_name(BACK_BUTTON)
+        )
+    };
+    sections.push(TextSection::new(help, style(Color::WHITE)));
+
+    for mut text in query.iter_mut() {
+        text.sections.clone_from(&sections);
+    }
+}
+
+pub struct RebindPlugin;
+
+impl Plugin for RebindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Bindings), spawn_bindings_screen)
+            .add_systems(
+                Update,
+                (bindings_input_system, update_bindings_text)
+                    .chain()
+                    .run_if(in_state(AppState::Bindings)),
+            )
+            .add_systems(OnExit(AppState::Bindings), despawn_bindings_screen);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_adds_to_an_action_and_takes_from_the_others() {
+        let mut keys = BTreeMap::from([
+            (Action::RotateLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+            (
+                Action::RotateRight,
+                vec![KeyCode::KeyD, KeyCode::ArrowRight],
+            ),
+        ]);
+
+        bind(&mut keys, Action::RotateLeft, KeyCode::KeyQ);
+        assert_eq!(
+            keys[&Action::RotateLeft],
+            [KeyCode::KeyA, KeyCode::ArrowLeft, KeyCode::KeyQ]
+        );
+
+        bind(&mut keys, Action::RotateLeft, KeyCode::KeyD);
+        assert_eq!(
+            keys[&Action::RotateLeft],
+            [
+                KeyCode::KeyA,
+                KeyCode::ArrowLeft,
+                KeyCode::KeyQ,
+                KeyCode::KeyD
+            ]
+        );
+        assert_eq!(keys[&Action::RotateRight], [KeyCode::ArrowRight]);
+
+        bind(&mut keys, Action::RotateLeft, KeyCode::KeyA);
+        assert_eq!(keys[&Action::RotateLeft].len(), 4);
+    }
+}
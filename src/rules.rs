@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scenario::Scenarios;
+use crate::score::GameClock;
+use crate::{AppState, WaveStartEvent};
+
+const AIRCRAFT_ESCAPE_PENALTY: i32 = 3;
+const UNDELIVERED_PARATROOPER_PENALTY: i32 = 1;
+/// Seconds of game time per wave
+pub const WAVE_DURATION: f32 = 60.;
+
+/*
+  A wave is a fixed slice of game time, `WAVE_DURATION` long, and not a group of spawns: the
+  spawners keep running at random throughout and know nothing of waves. Music, weather and the
+  developer console's `wave` command all go by this clock.
+*/
+
+/// Number of players and how they play
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Two guns spaced along the ground, one per player
+    CoOp,
+    /// A second player on a gamepad commands the enemy
+    Versus,
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::CoOp => "Co-op",
+            GameMode::Versus => "Versus",
+        }
+    }
+
+    /// The mode after this one in the menu
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Classic => GameMode::CoOp,
+            GameMode::CoOp => GameMode::Versus,
+            GameMode::Versus => GameMode::Classic,
+        }
+    }
+}
+
+/// How hard the enemy presses the attack
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn previous(&self) -> Difficulty {
+        self.next().next()
+    }
+
+    /// Scales how often aircraft and bombers appear
+    pub fn spawn_rate(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+}
+
+/// How score is credited when more than one gun is in play
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ScoreSharing {
+    /// One team score
+    #[default]
+    Shared,
+    /// Each player also keeps their own score, credited by whose bullet did the work
+    Split,
+}
+
+impl ScoreSharing {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreSharing::Shared => "Shared",
+            ScoreSharing::Split => "Split",
+        }
+    }
+
+    pub fn next(&self) -> ScoreSharing {
+        match self {
+            ScoreSharing::Shared => ScoreSharing::Split,
+            ScoreSharing::Split => ScoreSharing::Shared,
+        }
+    }
+}
+
+/// Configurable scoring penalties and loss conditions
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct GameRules {
+    /// Score lost for every aircraft that leaves the screen
+    pub aircraft_escape_penalty: i32,
+    /// Score lost for every paratrooper still on board an escaped aircraft
+    pub undelivered_paratrooper_penalty: i32,
+    /// Game over once this many aircraft have escaped. `None` disables the rule.
+    pub max_aircraft_escapes: Option<u32>,
+    pub score_sharing: ScoreSharing,
+}
+
+impl Default for GameRules {
+    fn default() -> GameRules {
+        GameRules {
+            aircraft_escape_penalty: AIRCRAFT_ESCAPE_PENALTY,
+            undelivered_paratrooper_penalty: UNDELIVERED_PARATROOPER_PENALTY,
+            max_aircraft_escapes: None,
+            score_sharing: ScoreSharing::default(),
+        }
+    }
+}
+
+/// The attack is continuous, so waves are stretches of game time. They pace the music and
+/// change the weather.
+#[derive(Resource, Default)]
+pub struct Wave {
+    /// Zero before the first wave starts
+    pub number: u32,
+}
+
+fn reset_wave(mut wave: ResMut<Wave>) {
+    *wave = Wave::default();
+}
+
+fn wave_system(
+    game_clock: Res<GameClock>,
+    mut wave: ResMut<Wave>,
+    mut event_writer: EventWriter<WaveStartEvent>,
+) {
+    let number = 1 + (game_clock.duration().as_secs_f32() / WAVE_DURATION) as u32;
+    if number != wave.number {
+        wave.number = number;
+        event_writer.send(WaveStartEvent(number));
+    }
+}
+
+/// Run condition for the random aircraft and bomber spawners. Scenarios and the versus player
+/// take over from them.
+pub fn random_spawns_enabled(game_mode: Res<GameMode>, scenarios: Res<Scenarios>) -> bool {
+    *game_mode != GameMode::Versus && scenarios.selected().is_none()
+}
+
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRules>()
+            .init_resource::<GameMode>()
+            .init_resource::<Difficulty>()
+            .init_resource::<Wave>()
+            .add_systems(OnEnter(AppState::NewGame), reset_wave)
+            .add_systems(Update, wave_system.run_if(in_state(AppState::InGame)));
+    }
+}
@@ -0,0 +1,576 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::aircraft::{
+    spawn_aircraft, AircraftTextures, DropZone, AIRCRAFT_SPEED, PARATROOPER_STICK_SIZE,
+};
+use crate::bomber::{spawn_bomber, BomberTextures, BOMBER_SPEED};
+use crate::menu::FontHandles;
+use crate::score::{GameClock, Score};
+use crate::session::GameSession;
+use crate::weather::Wind;
+use crate::AppState;
+
+const SCENARIO_DIR: &str = "assets/scenarios";
+const MESSAGE_FONT_SIZE: f32 = 40.;
+const MESSAGE_COLOR: Color = Color::GOLD;
+/// Seconds a message stays up when the scenario doesn't say
+const DEFAULT_MESSAGE_SECONDS: f32 = 4.;
+
+/*
+  A scenario is a RON file in assets/scenarios listing timed events, played in place of the
+  random aircraft and bomber spawners. Scenarios make hand-built levels and tutorials, and
+  repeatable runs for testing: `--scenario <file>` on the command line plays that file.
+
+      Scenario(
+          name: "First Contact",
+          events: [
+              (time: 2., action: Message(text: "Shoot down the aircraft")),
+              (time: 3., action: SpawnAircraft(side: Left, altitude: 250., stick: 3, drop_x: -100.)),
+              (time: 20., action: SetWind(40.)),
+              (time: 60., action: Check(AircraftKills(1))),
+          ],
+      )
+
+  A check ends the scenario, won if its condition holds and lost otherwise. With no check the
+  game carries on after the last event, with nothing more flying in. Paratroopers jump one
+  after another from `drop_x` when an aircraft has one, and at random otherwise. An optional
+  `seed` picks the random jumps and when chutes open, the same on every run.
+*/
+
+/// Edge of the screen a plane flies in from
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// What a scenario needs to have happened to be won
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum WinCondition {
+    /// At least this many aircraft and bombers shot down
+    AircraftKills(u32),
+    BombKills(u32),
+    /// Total score at least this
+    Score(i32),
+    /// No more than this many paratroopers landed
+    MaxParatroopersLanded(u32),
+    /// No more than this many aircraft got away
+    MaxAircraftEscapes(u32),
+}
+
+impl WinCondition {
+    fn is_met(&self, score: &Score) -> bool {
+        match *self {
+            WinCondition::AircraftKills(kills) => score.aircraft_kills >= kills,
+            WinCondition::BombKills(kills) => score.bomb_kills >= kills,
+            WinCondition::Score(total) => score.total_score >= total,
+            WinCondition::MaxParatroopersLanded(landed) => score.paratroopers_landed <= landed,
+            WinCondition::MaxAircraftEscapes(escapes) => score.aircraft_escapes <= escapes,
+        }
+    }
+}
+
+fn default_aircraft_speed() -> f32 {
+    AIRCRAFT_SPEED
+}
+
+fn default_bomber_speed() -> f32 {
+    BOMBER_SPEED
+}
+
+fn default_stick() -> usize {
+    PARATROOPER_STICK_SIZE
+}
+
+fn default_message_seconds() -> f32 {
+    DEFAULT_MESSAGE_SECONDS
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ScenarioAction {
+    SpawnAircraft {
+        side: Side,
+        altitude: f32,
+        #[serde(default = "default_aircraft_speed")]
+        speed: f32,
+        /// Paratroopers on board
+        #[serde(default = "default_stick")]
+        stick: usize,
+        /// Where the first paratrooper jumps
+        #[serde(default)]
+        drop_x: Option<f32>,
+    },
+    SpawnBomber {
+        side: Side,
+        altitude: f32,
+        #[serde(default = "default_bomber_speed")]
+        speed: f32,
+    },
+    /// Pixels per second, positive blowing right
+    SetWind(f32),
+    Message {
+        text: String,
+        #[serde(default = "default_message_seconds")]
+        seconds: f32,
+    },
+    Check(WinCondition),
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ScenarioEvent {
+    /// Seconds of game time from the start
+    pub time: f32,
+    pub action: ScenarioAction,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Seeds the paratrooper drops
+    #[serde(default)]
+    pub seed: u64,
+    pub events: Vec<ScenarioEvent>,
+}
+
+impl Scenario {
+    /// Read a scenario file, with its events put in time order
+    pub fn load(path: &str) -> Option<Scenario> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| warn!("Could not read {}: {}", path, error))
+            .ok()?;
+        let mut scenario: Scenario = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str(&contents)
+            .map_err(|error| warn!("Ignoring unreadable {}: {}", path, error))
+            .ok()?;
+        scenario.events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Some(scenario)
+    }
+}
+
+/// Scenarios to choose from on the main menu, and which one the next game plays
+#[derive(Resource, Default)]
+pub struct Scenarios {
+    scenarios: Vec<Scenario>,
+    /// `None` is the usual endless game
+    selected: Option<usize>,
+}
+
+impl Scenarios {
+    pub fn selected(&self) -> Option<&Scenario> {
+        self.selected.map(|index| &self.scenarios[index])
+    }
+
+    pub fn name(&self) -> &str {
+        self.selected()
+            .map_or("Endless", |scenario| scenario.name.as_str())
+    }
+
+    /// The next scenario in the menu, then back to the endless game
+    pub fn select_next(&mut self) {
+        self.selected = match self.selected {
+            None if !self.scenarios.is_empty() => Some(0),
+            Some(index) if index + 1 < self.scenarios.len() => Some(index + 1),
+            _ => None,
+        };
+    }
+
+    /// Add a scenario and play it next
+    pub fn select(&mut self, scenario: Scenario) {
+        self.scenarios.push(scenario);
+        self.selected = Some(self.scenarios.len() - 1);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScenarioOutcome {
+    Won,
+    Lost,
+}
+
+/// How far through the selected scenario the current game is
+#[derive(Resource, Default)]
+pub struct ScenarioProgress {
+    next_event: usize,
+    /// Set once a check ends the scenario
+    pub outcome: Option<ScenarioOutcome>,
+}
+
+/// Scenario text shown across the top of the screen, until the timer runs out or the game ends
+#[derive(Component)]
+struct ScenarioMessage(Option<Timer>);
+
+/// Scenario files in the scenario directory, in name order
+fn scenario_paths() -> Vec<String> {
+    let mut paths: Vec<String> = fs::read_dir(SCENARIO_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Find the scenario files, and pick one given on the command line
+fn load_scenarios(mut scenarios: ResMut<Scenarios>) {
+    scenarios.scenarios = scenario_paths()
+        .iter()
+        .filter_map(|path| Scenario::load(path))
+        .collect();
+
+    let mut args = std::env::args()
+        .skip_while(|arg| arg != "--scenario")
+        .skip(1);
+    if let Some(path) = args.next() {
+        if let Some(scenario) = Scenario::load(&path) {
+            scenarios.select(scenario);
+        }
+    }
+}
+
+fn reset_scenario(mut progress: ResMut<ScenarioProgress>) {
+    *progress = ScenarioProgress::default();
+}
+
+fn scenario_selected(scenarios: Res<Scenarios>) -> bool {
+    scenarios.selected().is_some()
+}
+
+fn show_message(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    message_query: &Query<Entity, With<ScenarioMessage>>,
+    text: &str,
+    seconds: Option<f32>,
+) {
+    for entity in message_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(80.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ScenarioMessage(
+            seconds.map(|seconds| Timer::from_seconds(seconds, TimerMode::Once)),
+        ))
+        .insert(GameSession)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font,
+                    font_size: MESSAGE_FONT_SIZE,
+                    color: MESSAGE_COLOR,
+                },
+            ));
+        });
+}
+
+/// Run every event that has come due on the game clock
+#[allow(clippy::too_many_arguments)]
+fn play_scenario(
+    mut commands: Commands,
+    scenarios: Res<Scenarios>,
+    game_clock: Res<GameClock>,
+    score: Res<Score>,
+    fonts: Res<FontHandles>,
+    aircraft_textures: Res<AircraftTextures>,
+    bomber_textures: Res<BomberTextures>,
+    mut progress: ResMut<ScenarioProgress>,
+    mut wind: ResMut<Wind>,
+    mut next_state: ResMut<NextState<AppState>>,
+    message_query: Query<Entity, With<ScenarioMessage>>,
+) {
+    let Some(scenario) = scenarios.selected() else {
+        return;
+    };
+    if progress.outcome.is_some() {
+        return;
+    }
+    let now = game_clock.duration().as_secs_f32();
+    while let Some(event) = scenario.events.get(progress.next_event) {
+        if event.time > now {
+            break;
+        }
+        progress.next_event += 1;
+        match &event.action {
+            ScenarioAction::SpawnAircraft {
+                side,
+                altitude,
+                speed,
+                stick,
+                drop_x,
+            } => {
+                let aircraft = spawn_aircraft(
+                    &mut commands,
+                    &aircraft_textures,
+                    *side == Side::Left,
+                    *altitude,
+                    *speed,
+                    *stick,
+                );
+                if let Some(x) = drop_x {
+                    commands.entity(aircraft).insert(DropZone::new(*x));
+                }
+            }
+            ScenarioAction::SpawnBomber {
+                side,
+                altitude,
+                speed,
+            } => {
+                spawn_bomber(
+                    &mut commands,
+                    &bomber_textures,
+                    *side == Side::Left,
+                    *altitude,
+                    *speed,
+                );
+            }
+            ScenarioAction::SetWind(speed) => wind.0 = *speed,
+            ScenarioAction::Message { text, seconds } => {
+                show_message(
+                    &mut commands,
+                    fonts.handle.clone(),
+                    &message_query,
+                    text,
+                    Some(*seconds),
+                );
+            }
+            ScenarioAction::Check(condition) => {
+                let (outcome, text) = if condition.is_met(&score) {
+                    (ScenarioOutcome::Won, "Mission complete")
+                } else {
+                    (ScenarioOutcome::Lost, "Mission failed")
+                };
+                info!("{}: {:?} at {:?}", scenario.name, outcome, condition);
+                progress.outcome = Some(outcome);
+                show_message(
+                    &mut commands,
+                    fonts.handle.clone(),
+                    &message_query,
+                    text,
+                    None,
+                );
+                next_state.set(AppState::GameOver);
+                break;
+            }
+        }
+    }
+}
+
+fn expire_messages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut message_query: Query<(Entity, &mut ScenarioMessage)>,
+) {
+    for (entity, mut message) in message_query.iter_mut() {
+        if let Some(timer) = message.0.as_mut() {
+            if timer.tick(time.delta()).finished() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Scenarios>()
+            .init_resource::<ScenarioProgress>()
+            .add_systems(Startup, load_scenarios)
+            .add_systems(OnEnter(AppState::NewGame), reset_scenario)
+            .add_systems(
+                Update,
+                (play_scenario, expire_messages)
+                    .chain()
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(scenario_selected),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::schedule::{ExecutorKind, Schedules};
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    use crate::aircraft::{Aircraft, AircraftPlugin};
+    use crate::animation::AnimationPlugin;
+    use crate::bomber::BomberPlugin;
+    use crate::map::MapPlugin;
+    use crate::paratrooper::{Paratrooper, ParatrooperPlugin};
+    use crate::particle::ParticlePlugin;
+    use crate::rules::RulesPlugin;
+    use crate::score::ScorePlugin;
+    use crate::session::SessionPlugin;
+    use crate::terrain::TerrainPlugin;
+    use crate::weather::WeatherPlugin;
+    use crate::EventPlugin;
+
+    /// Game time per update. Rapier steps at most 1/60 s per update, so anything longer would
+    /// slow the physics down against the game clock.
+    const STEP: Duration = Duration::from_nanos(16_666_667);
+
+    /// What flew in and who jumped where, as the game went
+    #[derive(Resource, Default)]
+    struct FlightLog {
+        aircraft: usize,
+        jumps: Vec<f32>,
+    }
+
+    fn log_flights(
+        mut log: ResMut<FlightLog>,
+        aircraft_query: Query<(), Added<Aircraft>>,
+        paratrooper_query: Query<&Transform, Added<Paratrooper>>,
+    ) {
+        log.aircraft += aircraft_query.iter().count();
+        log.jumps.extend(
+            paratrooper_query
+                .iter()
+                .map(|transform| transform.translation.x),
+        );
+    }
+
+    struct Played {
+        outcome: ScenarioOutcome,
+        score: Score,
+        log: FlightLog,
+    }
+
+    fn load(file: &str) -> Scenario {
+        Scenario::load(&format!("{}/{}", SCENARIO_DIR, file)).unwrap()
+    }
+
+    #[test]
+    fn scenario_files_load() {
+        let paths = scenario_paths();
+        assert!(!paths.is_empty(), "No scenarios in {}", SCENARIO_DIR);
+        for path in paths {
+            let scenario = Scenario::load(&path).unwrap_or_else(|| panic!("{} did not load", path));
+            assert!(!scenario.events.is_empty(), "{} has no events", path);
+        }
+    }
+
+    /// Run the gameplay plugins without a window until the scenario's check ends the game
+    fn play(scenario: Scenario) -> Played {
+        let check_time = scenario
+            .events
+            .iter()
+            .find(|event| matches!(event.action, ScenarioAction::Check(_)))
+            .expect("scenario has a check")
+            .time;
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+        ))
+        .init_asset::<Image>()
+        .init_asset::<Font>()
+        .init_asset::<TextureAtlasLayout>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(STEP))
+        .insert_resource(FontHandles {
+            handle: Handle::default(),
+        })
+        .insert_state(AppState::MainMenu)
+        .add_plugins((
+            EventPlugin,
+            SessionPlugin,
+            RulesPlugin,
+            ScorePlugin,
+            TerrainPlugin,
+            MapPlugin,
+            WeatherPlugin,
+            ParticlePlugin,
+            AnimationPlugin,
+            AircraftPlugin,
+            BomberPlugin,
+            ParatrooperPlugin,
+            ScenarioPlugin,
+        ))
+        .init_resource::<FlightLog>()
+        .add_systems(Last, log_flights);
+        // One system at a time, in the same order every run
+        for (_, schedule) in app.world.resource_mut::<Schedules>().iter_mut() {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        }
+        app.update();
+
+        app.world.resource_mut::<Scenarios>().select(scenario);
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::NewGame);
+        let max_updates = (check_time / STEP.as_secs_f32()) as usize * 2;
+        for _ in 0..max_updates {
+            app.update();
+            if *app.world.resource::<State<AppState>>() == AppState::GameOver {
+                break;
+            }
+        }
+
+        let outcome = app
+            .world
+            .resource::<ScenarioProgress>()
+            .outcome
+            .expect("scenario reached its check");
+        let clock = app.world.resource::<GameClock>().duration().as_secs_f32();
+        assert!(
+            clock >= check_time,
+            "checked at {} before {}",
+            clock,
+            check_time
+        );
+        Played {
+            outcome,
+            score: *app.world.resource::<Score>(),
+            log: app.world.remove_resource::<FlightLog>().unwrap(),
+        }
+    }
+
+    #[test]
+    fn scenario_plays_to_its_check() {
+        let played = play(load("01_basic_training.ron"));
+        // Five aircraft, and nobody on the gun to stop them
+        assert_eq!(played.log.aircraft, 5);
+        assert_eq!(played.score.aircraft_escapes, 5);
+        // Sticks of 2, 2 and 3 jump over their drop zones, and all of them land by the check
+        assert_eq!(played.log.jumps.len(), 7);
+        assert_eq!(played.score.paratroopers_landed, 7);
+        // More than the 3 landings the check allows
+        assert_eq!(played.outcome, ScenarioOutcome::Lost);
+    }
+
+    /// Bomber Raid gives no drop zones, so where its troopers jump is down to the seed
+    #[test]
+    fn seeded_scenario_drops_the_same_way() {
+        let scenario = load("02_bomber_raid.ron");
+        let first = play(scenario.clone());
+        let second = play(scenario.clone());
+        let reseeded = play(Scenario {
+            seed: scenario.seed + 1,
+            ..scenario
+        });
+        assert!(!first.log.jumps.is_empty());
+        assert_eq!(first.log.jumps, second.log.jumps);
+        assert_ne!(first.log.jumps, reseeded.log.jumps);
+    }
+}
@@ -1,4 +1,7 @@
 use crate::consts::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::player::Player;
+use crate::rules::{GameRules, ScoreSharing};
+use crate::session::{GameSession, SessionCleanup};
 use crate::AppState;
 use bevy::prelude::*;
 use std::time::Duration;
@@ -9,6 +12,8 @@ use crate::paratrooper::PARATROOPER_SCALE;
 const SUBSCORE_COLOR: Color = Color::GOLD;
 const FONT_SIZE: f32 = 40.0;
 
+/// The `Score` resource is the team score. In multiplayer each gun also carries its player's
+/// `Score` component.
 #[derive(Component, Debug, Default, Clone, Copy, Resource)]
 pub struct Score {
     pub shots: u32,
@@ -16,6 +21,7 @@ pub struct Score {
     pub aircraft_escapes: u32,
     pub paratrooper_kills: u32,
     pub paratroopers_landed: u32,
+    pub paratroopers_undelivered: u32,
     pub parachute_hits: u32,
     pub bomb_kills: u32,
     pub total_score: i32,
@@ -54,6 +60,18 @@ pub struct GameClock {
     duration: Duration,
 }
 
+impl GameClock {
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Jump the clock, as when skipping ahead to a later wave
+    #[cfg(feature = "dev_console")]
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+}
+
 /// Score UI font and textures
 #[derive(Resource)]
 struct ScoreAssets {
@@ -83,6 +101,8 @@ fn setup_score_bar(mut commands: Commands, assets: Res<ScoreAssets>) {
             },
             ..default()
         })
+        .insert(ScoreBar)
+        .insert(GameSession)
         .with_children(|parent| {
             parent
                 .spawn(NodeBundle {
@@ -295,19 +315,42 @@ fn spawn_score_text(builder: &mut ChildBuilder, font: Handle<Font>) {
         });
 }
 
+/// Text of one score bar counter
+type CounterText<T> = Query<'static, 'static, &'static mut Text, With<T>>;
+/// Every score bar counter, taking turns at the text
+type CounterTexts<'w, 's> = ParamSet<
+    'w,
+    's,
+    (
+        CounterText<ScoreText>,
+        CounterText<BulletText>,
+        CounterText<AircraftText>,
+        CounterText<ParatrooperText>,
+        CounterText<BombText>,
+    ),
+>;
+
 fn update_score_bar(
-    mut set: ParamSet<(
-        Query<&mut Text, With<ScoreText>>,
-        Query<&mut Text, With<BulletText>>,
-        Query<&mut Text, With<AircraftText>>,
-        Query<&mut Text, With<ParatrooperText>>,
-        Query<&mut Text, With<BombText>>,
-    )>,
+    mut set: CounterTexts,
     score: Res<Score>,
+    rules: Res<GameRules>,
+    player_scores: Query<(&Player, &Score)>,
 ) {
     if score.is_changed() {
+        let score_string = match rules.score_sharing {
+            ScoreSharing::Split if player_scores.iter().count() > 1 => {
+                let mut player_scores: Vec<(&Player, &Score)> = player_scores.iter().collect();
+                player_scores.sort_by_key(|(player, _)| player.0);
+                player_scores
+                    .iter()
+                    .map(|(player, score)| format!("P{} {:05}", player.0 + 1, score.total_score))
+                    .collect::<Vec<String>>()
+                    .join("  ")
+            }
+            _ => format!("SCORE {:05}", score.total_score),
+        };
         for mut score_text in set.p0().iter_mut() {
-            score_text.sections[0].value = format!("SCORE {:05}", score.total_score);
+            score_text.sections[0].value = score_string.clone();
         }
         for mut bullet_text in set.p1().iter_mut() {
             bullet_text.sections[0].value = format!("{:04}", score.shots);
@@ -324,58 +367,118 @@ fn update_score_bar(
     }
 }
 
-fn despawn_score_bar(mut commands: Commands, query: Query<Entity, With<ScoreBar>>) {
-    for score_bar in query.iter() {
-        commands.entity(score_bar).despawn_recursive();
+/// Apply a score change to the team score and the credited player's score. Changes nobody in
+/// particular earned, like escapes, apply to every player.
+fn credit(
+    team_score: &mut Score,
+    player_scores: &mut Query<(&Player, &mut Score)>,
+    player: Option<Player>,
+    change: impl Fn(&mut Score),
+) {
+    change(team_score);
+    for (_, mut player_score) in player_scores
+        .iter_mut()
+        .filter(|(p, _)| player.is_none_or(|player| **p == player))
+    {
+        change(&mut player_score);
     }
 }
 
 /// Update score on bullet kills
-fn kill_listener_system(mut events: EventReader<BulletCollisionEvent>, mut score: ResMut<Score>) {
+fn kill_listener_system(
+    mut events: EventReader<BulletCollisionEvent>,
+    mut score: ResMut<Score>,
+    mut player_scores: Query<(&Player, &mut Score)>,
+) {
     for bullet_collision_event in events.read() {
-        match bullet_collision_event.collision_type {
-            CollisionType::Aircraft => {
-                score.aircraft_kills += 1;
-                score.total_score += AIRCRAFT_KILL_SCORE;
-            }
-            CollisionType::Parachute => score.parachute_hits += 1,
-            CollisionType::Bomb => {
-                score.bomb_kills += 1;
-                score.total_score += BOMB_KILL_SCORE;
+        let player = Some(bullet_collision_event.player);
+        credit(&mut score, &mut player_scores, player, |score| {
+            match bullet_collision_event.collision_type {
+                CollisionType::Aircraft => {
+                    score.aircraft_kills += 1;
+                    score.total_score += AIRCRAFT_KILL_SCORE;
+                }
+                CollisionType::Parachute => score.parachute_hits += 1,
+                CollisionType::Bomb => {
+                    score.bomb_kills += 1;
+                    score.total_score += BOMB_KILL_SCORE;
+                }
+                CollisionType::Paratrooper => (), // GibEvent covers
             }
-            CollisionType::Paratrooper => (), // GibEvent covers
-        }
+        });
     }
 }
 
-fn gib_listener_system(mut events: EventReader<GibEvent>, mut score: ResMut<Score>) {
-    for _e in events.read() {
-        score.paratrooper_kills += 1;
-        score.total_score += PARATROOPER_KILL_SCORE;
+fn gib_listener_system(
+    mut events: EventReader<GibEvent>,
+    mut score: ResMut<Score>,
+    mut player_scores: Query<(&Player, &mut Score)>,
+) {
+    for gib in events.read() {
+        credit(&mut score, &mut player_scores, gib.player, |score| {
+            score.paratrooper_kills += 1;
+            score.total_score += PARATROOPER_KILL_SCORE;
+        });
     }
 }
 
-fn gun_listener_system(mut events: EventReader<GunshotEvent>, mut score: ResMut<Score>) {
-    for _gunshot in events.read() {
-        score.shots += 1;
-        // Shots don't take score below 0
-        score.total_score = (score.total_score + SHOT_SCORE).max(0);
+fn gun_listener_system(
+    mut events: EventReader<GunshotEvent>,
+    mut score: ResMut<Score>,
+    mut player_scores: Query<(&Player, &mut Score)>,
+) {
+    for gunshot in events.read() {
+        let player = Some(gunshot.player);
+        credit(&mut score, &mut player_scores, player, |score| {
+            score.shots += 1;
+            // Shots don't take score below 0
+            score.total_score = (score.total_score + SHOT_SCORE).max(0);
+        });
     }
 }
 
-fn landing_listener_system(mut events: EventReader<LandingEvent>, mut score: ResMut<Score>) {
+fn landing_listener_system(
+    mut events: EventReader<LandingEvent>,
+    mut score: ResMut<Score>,
+    mut player_scores: Query<(&Player, &mut Score)>,
+) {
     for _landing in events.read() {
-        score.paratroopers_landed += 1;
+        credit(&mut score, &mut player_scores, None, |score| {
+            score.paratroopers_landed += 1;
+        });
     }
 }
 
-fn gun_explosion_listener_system(
-    mut events: EventReader<GunExplosionEvent>,
+/// Penalize escaped aircraft and the paratroopers they carried away
+fn escape_listener_system(
+    mut events: EventReader<AircraftEscapeEvent>,
+    mut score: ResMut<Score>,
+    mut player_scores: Query<(&Player, &mut Score)>,
+    rules: Res<GameRules>,
+) {
+    for escape in events.read() {
+        let paratroopers = escape.paratroopers as u32;
+        let penalty = rules.aircraft_escape_penalty
+            + rules.undelivered_paratrooper_penalty * paratroopers as i32;
+        credit(&mut score, &mut player_scores, None, |score| {
+            score.aircraft_escapes += 1;
+            score.paratroopers_undelivered += paratroopers;
+            // Penalties don't take score below 0
+            score.total_score = (score.total_score - penalty).max(0);
+        });
+    }
+}
+
+/// Too many escaped aircraft ends the game, if the rules say so
+fn escape_limit_system(
+    score: Res<Score>,
+    rules: Res<GameRules>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    if !events.is_empty() {
-        events.clear();
-        next_state.set(AppState::GameOver);
+    if let Some(max_escapes) = rules.max_aircraft_escapes {
+        if score.aircraft_escapes >= max_escapes {
+            next_state.set(AppState::GameOver);
+        }
     }
 }
 
@@ -385,7 +488,7 @@ fn update_clock_ui(game_clock: Res<GameClock>, mut query: Query<&mut Text, With<
     }
 }
 
-fn get_clock_string(duration: Duration) -> String {
+pub fn get_clock_string(duration: Duration) -> String {
     let minutes = (duration.as_secs_f32() / 60.).floor();
     let seconds = (duration.as_secs_f32() % 60.).floor();
     format!("{:02}:{:02}", minutes, seconds)
@@ -401,8 +504,7 @@ fn update_game_clock(mut game_clock: ResMut<GameClock>, time: Res<Time>) {
     game_clock.duration += Duration::from_secs_f64(time.delta_seconds_f64());
 }
 
-fn game_over(mut game_clock: ResMut<GameClock>, mut score: ResMut<Score>) {
-    game_clock.duration = Duration::ZERO;
+fn reset_score(mut score: ResMut<Score>) {
     *score = Score::default();
 }
 
@@ -413,8 +515,8 @@ impl Plugin for ScorePlugin {
         app.init_resource::<Score>()
             .add_systems(Startup, setup)
             .add_systems(
-                OnExit(AppState::MainMenu),
-                (setup_game_clock, setup_score_bar),
+                OnEnter(AppState::NewGame),
+                (setup_game_clock, setup_score_bar, reset_score).after(SessionCleanup),
             )
             .add_systems(
                 Update,
@@ -423,14 +525,13 @@ impl Plugin for ScorePlugin {
                     gib_listener_system,
                     gun_listener_system,
                     landing_listener_system,
-                    gun_explosion_listener_system,
+                    escape_listener_system,
+                    escape_limit_system.after(escape_listener_system),
                     update_game_clock,
                     update_clock_ui,
                     update_score_bar,
                 )
                     .run_if(in_state(AppState::InGame)),
-            )
-            .add_systems(OnExit(AppState::InGame), despawn_score_bar)
-            .add_systems(OnEnter(AppState::GameOver), game_over);
+            );
     }
 }
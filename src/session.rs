@@ -0,0 +1,37 @@
+use crate::AppState;
+use bevy::prelude::*;
+
+/// Tags every entity that belongs to a single game, from `AppState::NewGame` until the player
+/// returns to the main menu or starts another game.
+#[derive(Component, Default)]
+pub struct GameSession;
+
+/// Clearing out the previous game on `OnEnter(AppState::NewGame)`. Setup systems that spawn
+/// session entities run after it.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SessionCleanup;
+
+/// Despawn everything left over from the previous game
+fn despawn_session_entities(mut commands: Commands, query: Query<Entity, With<GameSession>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Session setup runs on `OnEnter(AppState::NewGame)`, then play begins
+fn start_session(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::InGame);
+}
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MainMenu), despawn_session_entities)
+            .add_systems(
+                OnEnter(AppState::NewGame),
+                despawn_session_entities.in_set(SessionCleanup),
+            )
+            .add_systems(Update, start_session.run_if(in_state(AppState::NewGame)));
+    }
+}
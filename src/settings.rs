@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::input::InputBindings;
+use crate::player::ControlScheme;
+use crate::rules::{Difficulty, GameRules, ScoreSharing};
+use crate::voice::VoiceLimits;
+
+/// Player settings live next to the executable's working directory
+const SETTINGS_PATH: &str = "settings.ron";
+
+/// Volume steps on the settings screen
+pub const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum WindowModeSetting {
+    #[default]
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowModeSetting::Windowed => "Windowed",
+            WindowModeSetting::BorderlessFullscreen => "Borderless",
+            WindowModeSetting::Fullscreen => "Fullscreen",
+        }
+    }
+
+    pub fn next(&self) -> WindowModeSetting {
+        match self {
+            WindowModeSetting::Windowed => WindowModeSetting::BorderlessFullscreen,
+            WindowModeSetting::BorderlessFullscreen => WindowModeSetting::Fullscreen,
+            WindowModeSetting::Fullscreen => WindowModeSetting::Windowed,
+        }
+    }
+
+    pub fn previous(&self) -> WindowModeSetting {
+        self.next().next()
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::BorderlessFullscreen => WindowMode::BorderlessFullscreen,
+            WindowModeSetting::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// Audio, video and gameplay options, changed on the settings screen
+#[derive(Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Volumes run from 0 to 1
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub voice_volume: f32,
+    pub window_mode: WindowModeSetting,
+    pub vsync: bool,
+    pub control_scheme: ControlScheme,
+    /// Blood and death screams
+    pub gore: bool,
+    /// Camera shake and hit-stop on big hits
+    pub screen_shake: bool,
+    /// Clouds hide the aircraft and troopers inside them
+    pub cloud_cover: bool,
+    /// Difficulty every game starts at
+    pub difficulty: Difficulty,
+    /// Game over once this many aircraft have escaped. `None` plays on regardless.
+    pub escape_limit: Option<u32>,
+    /// Team score only, or each co-op player's score too
+    pub score_sharing: ScoreSharing,
+    pub voice_limits: VoiceLimits,
+}
+
+impl Default for GameSettings {
+    fn default() -> GameSettings {
+        GameSettings {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            voice_volume: 1.0,
+            window_mode: WindowModeSetting::default(),
+            vsync: true,
+            control_scheme: ControlScheme::default(),
+            gore: true,
+            screen_shake: true,
+            cloud_cover: false,
+            difficulty: Difficulty::default(),
+            escape_limit: None,
+            score_sharing: ScoreSharing::default(),
+            voice_limits: VoiceLimits::default(),
+        }
+    }
+}
+
+/// Everything saved to the settings file. Missing fields fall back to defaults, so older files
+/// keep loading as settings are added.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SettingsFile {
+    settings: GameSettings,
+    controls: InputBindings,
+}
+
+/// Read a RON file, falling back to the default when it is missing or unreadable
+pub fn read_ron_file<T: DeserializeOwned + Default>(path: &str) -> T {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return T::default();
+    };
+    ron::from_str(&contents).unwrap_or_else(|error| {
+        warn!("Ignoring unreadable {}: {}", path, error);
+        T::default()
+    })
+}
+
+/// Write a RON file, logging rather than failing
+pub fn write_ron_file<T: Serialize>(path: &str, value: &T) {
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|contents| fs::write(path, contents).map_err(|error| error.to_string()));
+    if let Err(error) = result {
+        warn!("Could not save {}: {}", path, error);
+    }
+}
+
+fn load_settings(mut commands: Commands) {
+    let settings_file: SettingsFile = read_ron_file(SETTINGS_PATH);
+    commands.insert_resource(settings_file.settings);
+    commands.insert_resource(settings_file.controls);
+}
+
+/// Write the settings file whenever a setting changes
+fn save_settings(settings: Res<GameSettings>, bindings: Res<InputBindings>) {
+    if settings.is_added() && bindings.is_added() {
+        return;
+    }
+    let settings_file = SettingsFile {
+        settings: settings.clone(),
+        controls: bindings.clone(),
+    };
+    write_ron_file(SETTINGS_PATH, &settings_file);
+}
+
+/// Push settings to the window and game resources, at startup and after every change
+fn apply_settings(
+    settings: Res<GameSettings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut control_scheme: ResMut<ControlScheme>,
+    mut difficulty: ResMut<Difficulty>,
+    mut rules: ResMut<GameRules>,
+    mut voice_limits: ResMut<VoiceLimits>,
+) {
+    for mut window in window_query.iter_mut() {
+        window.mode = settings.window_mode.window_mode();
+        window.present_mode = if settings.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+    }
+    *control_scheme = settings.control_scheme;
+    *difficulty = settings.difficulty;
+    rules.max_aircraft_escapes = settings.escape_limit;
+    rules.score_sharing = settings.score_sharing;
+    voice_limits.clone_from(&settings.voice_limits);
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_settings)
+            .add_systems(
+                PreUpdate,
+                apply_settings.run_if(resource_changed::<GameSettings>),
+            )
+            .add_systems(
+                Last,
+                save_settings.run_if(
+                    resource_changed::<GameSettings>.or_else(resource_changed::<InputBindings>),
+                ),
+            );
+    }
+}
@@ -0,0 +1,351 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::menu::{AppState, FontHandles};
+use crate::menu_button::{spawn_menu_button, ButtonMenu, MenuButton, MenuButtonSet, BUTTON_HEIGHT};
+use crate::player::ControlScheme;
+use crate::settings::{GameSettings, VOLUME_STEP};
+use crate::voice::SoundCategory;
+use crate::MenuButtonPressed;
+
+/// Choices for the escaped aircraft limit, in menu order
+const ESCAPE_LIMITS: [Option<u32>; 4] = [None, Some(5), Some(10), Some(20)];
+/// Voice limits run from 1 to this
+const MAX_VOICE_LIMIT: usize = 12;
+/// Buttons per column on the settings screen, which wraps into a second column
+const BUTTONS_PER_COLUMN: f32 = 8.;
+
+/// Where the settings screen goes back to: the main menu or the pause menu
+#[derive(Resource, Default)]
+pub struct SettingsReturnState(pub AppState);
+
+#[derive(Component)]
+struct SettingsScreen;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum SettingsButton {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    VoiceVolume,
+    /// Most sounds of the category playing at once
+    VoiceLimit(SoundCategory),
+    WindowMode,
+    Vsync,
+    ControlScheme,
+    Gore,
+    ScreenShake,
+    Difficulty,
+    CloudCover,
+    EscapeLimit,
+    ScoreSharing,
+    Controls,
+    Back,
+}
+
+impl SettingsButton {
+    const ALL: [SettingsButton; 19] = [
+        SettingsButton::MasterVolume,
+        SettingsButton::MusicVolume,
+        SettingsButton::SfxVolume,
+        SettingsButton::VoiceVolume,
+        SettingsButton::VoiceLimit(SoundCategory::Gunshot),
+        SettingsButton::VoiceLimit(SoundCategory::Explosion),
+        SettingsButton::VoiceLimit(SoundCategory::Scream),
+        SettingsButton::VoiceLimit(SoundCategory::Whistle),
+        SettingsButton::WindowMode,
+        SettingsButton::Vsync,
+        SettingsButton::ControlScheme,
+        SettingsButton::Gore,
+        SettingsButton::ScreenShake,
+        SettingsButton::Difficulty,
+        SettingsButton::CloudCover,
+        SettingsButton::EscapeLimit,
+        SettingsButton::ScoreSharing,
+        SettingsButton::Controls,
+        SettingsButton::Back,
+    ];
+
+    fn label(&self, settings: &GameSettings) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        let percent = |volume: f32| format!("{:.0}%", volume * 100.);
+        match self {
+            SettingsButton::MasterVolume => format!("Volume: {}", percent(settings.master_volume)),
+            SettingsButton::MusicVolume => format!("Music: {}", percent(settings.music_volume)),
+            SettingsButton::SfxVolume => format!("Effects: {}", percent(settings.sfx_volume)),
+            SettingsButton::VoiceVolume => format!("Voices: {}", percent(settings.voice_volume)),
+            SettingsButton::VoiceLimit(category) => format!(
+                "{} sounds: {}",
+                category.name(),
+                settings.voice_limits.limit(*category)
+            ),
+            SettingsButton::WindowMode => format!("Window: {}", settings.window_mode.name()),
+            SettingsButton::Vsync => format!("Vsync: {}", on_off(settings.vsync)),
+            SettingsButton::ControlScheme => format!(
+                "Mouse aiming: {}",
+                on_off(settings.control_scheme == ControlScheme::Mouse)
+            ),
+            SettingsButton::Gore => format!("Gore: {}", on_off(settings.gore)),
+            SettingsButton::ScreenShake => {
+                format!("Screen shake: {}", on_off(settings.screen_shake))
+            }
+            SettingsButton::Difficulty => format!("Difficulty: {}", settings.difficulty.name()),
+            SettingsButton::CloudCover => format!("Cloud cover: {}", on_off(settings.cloud_cover)),
+            SettingsButton::EscapeLimit => match settings.escape_limit {
+                Some(limit) => format!("Escapes allowed: {}", limit),
+                None => "Escapes allowed: Any".to_string(),
+            },
+            SettingsButton::ScoreSharing => {
+                format!("Co-op scores: {}", settings.score_sharing.name())
+            }
+            SettingsButton::Controls => "Controls".to_string(),
+            SettingsButton::Back => "Back".to_string(),
+        }
+    }
+
+    /// Step the setting forward or back. Volumes stop at the ends, choices wrap around.
+    fn adjust(&self, settings: &mut GameSettings, forward: bool) {
+        let step = |volume: f32| {
+            let delta = if forward { VOLUME_STEP } else { -VOLUME_STEP };
+            // Round so repeated steps land on whole percentages
+            ((volume + delta).clamp(0., 1.) * 100.).round() / 100.
+        };
+        match self {
+            SettingsButton::MasterVolume => settings.master_volume = step(settings.master_volume),
+            SettingsButton::MusicVolume => settings.music_volume = step(settings.music_volume),
+            SettingsButton::SfxVolume => settings.sfx_volume = step(settings.sfx_volume),
+            SettingsButton::VoiceVolume => settings.voice_volume = step(settings.voice_volume),
+            SettingsButton::VoiceLimit(category) => {
+                let limit = settings.voice_limits.limit_mut(*category);
+                *limit = if forward {
+                    *limit % MAX_VOICE_LIMIT + 1
+                } else {
+                    (*limit + MAX_VOICE_LIMIT - 2) % MAX_VOICE_LIMIT + 1
+                };
+            }
+            SettingsButton::WindowMode => {
+                settings.window_mode = if forward {
+                    settings.window_mode.next()
+                } else {
+                    settings.window_mode.previous()
+                }
+            }
+            SettingsButton::Vsync => settings.vsync = !settings.vsync,
+            SettingsButton::ControlScheme => {
+                settings.control_scheme = match settings.control_scheme {
+                    ControlScheme::Keys => ControlScheme::Mouse,
+                    ControlScheme::Mouse => ControlScheme::Keys,
+                }
+            }
+            SettingsButton::Gore => settings.gore = !settings.gore,
+            SettingsButton::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsButton::Difficulty => {
+                settings.difficulty = if forward {
+                    settings.difficulty.next()
+                } else {
+                    settings.difficulty.previous()
+                }
+            }
+            SettingsButton::CloudCover => settings.cloud_cover = !settings.cloud_cover,
+            SettingsButton::EscapeLimit => {
+                let count = ESCAPE_LIMITS.len();
+                let index = ESCAPE_LIMITS
+                    .iter()
+                    .position(|&limit| limit == settings.escape_limit)
+                    .unwrap_or(0);
+                let index = if forward {
+                    (index + 1) % count
+                } else {
+                    (index + count - 1) % count
+                };
+                settings.escape_limit = ESCAPE_LIMITS[index];
+            }
+            SettingsButton::ScoreSharing => settings.score_sharing = settings.score_sharing.next(),
+            SettingsButton::Controls | SettingsButton::Back => (),
+        }
+    }
+
+    fn is_volume(&self) -> bool {
+        matches!(
+            self,
+            SettingsButton::MasterVolume
+                | SettingsButton::MusicVolume
+                | SettingsButton::SfxVolume
+                | SettingsButton::VoiceVolume
+        )
+    }
+}
+
+fn spawn_settings_screen(mut commands: Commands, font_handles: Res<FontHandles>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.5).into(),
+            ..default()
+        })
+        .insert(SettingsScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "SETTINGS",
+                TextStyle {
+                    font: font_handles.handle.clone(),
+                    font_size: 50.0,
+                    color: Color::RED,
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        flex_wrap: FlexWrap::Wrap,
+                        max_height: Val::Px(BUTTONS_PER_COLUMN * (BUTTON_HEIGHT + 6.)),
+                        row_gap: Val::Px(6.),
+                        column_gap: Val::Px(20.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(ButtonMenu::default())
+                .with_children(|parent| {
+                    for (index, button) in SettingsButton::ALL.into_iter().enumerate() {
+                        spawn_menu_button(parent, font_handles.handle.clone(), "", index)
+                            .insert(button);
+                    }
+                });
+        });
+}
+
+fn despawn_settings_screen(mut commands: Commands, query: Query<Entity, With<SettingsScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Leaving the settings screen, for the bindings screen or back where it came from
+#[derive(SystemParam)]
+struct SettingsExit<'w> {
+    return_state: Res<'w, SettingsReturnState>,
+    next_state: ResMut<'w, NextState<AppState>>,
+}
+
+impl SettingsExit<'_> {
+    fn back(&mut self) {
+        self.next_state.set(self.return_state.0.clone());
+    }
+}
+
+/// Confirm or a click steps a setting forward, wrapping volumes back to zero. Left and right
+/// step the selected setting either way. Esc or East goes back.
+fn settings_menu_system(
+    mut event_reader: EventReader<MenuButtonPressed>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    button_inputs: Res<ButtonInput<GamepadButton>>,
+    menu_query: Query<(&ButtonMenu, &Children)>,
+    button_query: Query<(&MenuButton, &SettingsButton)>,
+    mut settings: ResMut<GameSettings>,
+    mut exit: SettingsExit,
+) {
+    let gamepad_pressed = |button_type: GamepadButtonType| {
+        button_inputs
+            .get_just_pressed()
+            .any(|button| button.button_type == button_type)
+    };
+
+    for event in event_reader.read() {
+        let Ok((_, &button)) = button_query.get(event.0) else {
+            continue;
+        };
+        match button {
+            SettingsButton::Controls => exit.next_state.set(AppState::Bindings),
+            SettingsButton::Back => exit.back(),
+            _ if button.is_volume() && settings_volume(&settings, button) >= 1. => {
+                set_volume(&mut settings, button, 0.);
+            }
+            _ => button.adjust(&mut settings, true),
+        }
+    }
+
+    let left = keyboard_input.just_pressed(KeyCode::ArrowLeft)
+        || gamepad_pressed(GamepadButtonType::DPadLeft);
+    let right = keyboard_input.just_pressed(KeyCode::ArrowRight)
+        || gamepad_pressed(GamepadButtonType::DPadRight);
+    if left || right {
+        for (menu, children) in menu_query.iter() {
+            let selected = children
+                .iter()
+                .filter_map(|&child| button_query.get(child).ok())
+                .find(|(menu_button, _)| menu_button.index == menu.selected);
+            if let Some((_, button)) = selected {
+                button.adjust(&mut settings, right);
+            }
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) || gamepad_pressed(GamepadButtonType::East) {
+        exit.back();
+    }
+}
+
+fn settings_volume(settings: &GameSettings, button: SettingsButton) -> f32 {
+    match button {
+        SettingsButton::MasterVolume => settings.master_volume,
+        SettingsButton::MusicVolume => settings.music_volume,
+        SettingsButton::SfxVolume => settings.sfx_volume,
+        SettingsButton::VoiceVolume => settings.voice_volume,
+        _ => 0.,
+    }
+}
+
+fn set_volume(settings: &mut GameSettings, button: SettingsButton, volume: f32) {
+    match button {
+        SettingsButton::MasterVolume => settings.master_volume = volume,
+        SettingsButton::MusicVolume => settings.music_volume = volume,
+        SettingsButton::SfxVolume => settings.sfx_volume = volume,
+        SettingsButton::VoiceVolume => settings.voice_volume = volume,
+        _ => (),
+    }
+}
+
+fn update_settings_labels(
+    settings: Res<GameSettings>,
+    button_query: Query<(&SettingsButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children) in button_query.iter() {
+        let label = button.label(&settings);
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            if text.sections[0].value != label {
+                text.sections[0].value.clone_from(&label);
+            }
+        }
+    }
+}
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsReturnState>()
+            .add_systems(OnEnter(AppState::Settings), spawn_settings_screen)
+            .add_systems(
+                Update,
+                (
+                    settings_menu_system.after(MenuButtonSet),
+                    update_settings_labels,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Settings)),
+            )
+            .add_systems(OnExit(AppState::Settings), despawn_settings_screen);
+    }
+}
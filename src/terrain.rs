@@ -1,4 +1,5 @@
 use crate::consts;
+use crate::daylight::Scenery;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
@@ -41,6 +42,9 @@ fn setup_ground(mut commands: Commands) {
             coefficient: 0.0,
             combine_rule: CoefficientCombineRule::Min,
         })
+        .insert(Scenery {
+            color: GROUND_COLOR,
+        })
         .insert(Ground);
 }
 
@@ -48,28 +52,11 @@ fn setup_physics(mut configuration: ResMut<RapierConfiguration>) {
     configuration.gravity = Vec2::Y * consts::GRAVITY;
 }
 
-fn setup_skyline(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let width = 367.;
-    let height = 109.;
-    let scale_multiplier = consts::WINDOW_WIDTH / width;
-    let scale = Vec3::splat(scale_multiplier);
-    let y = consts::GROUND_Y + 0.5 * height * scale_multiplier;
-    commands.spawn(SpriteBundle {
-        texture: asset_server.load("images/city4.png"),
-        transform: Transform {
-            translation: Vec3::new(0., y, 0.),
-            scale,
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-}
-
 pub struct TerrainPlugin;
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (setup_ground, setup_skyline, setup_physics))
+        app.add_systems(Startup, (setup_ground, setup_physics))
             .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(10.));
     }
 }
@@ -0,0 +1,372 @@
+use crate::aircraft::{
+    spawn_aircraft, AircraftTextures, DropZone, AIRCRAFT_SPEED, PARATROOPER_STICK_SIZE,
+    SPAWN_Y_MAX, SPAWN_Y_MIN,
+};
+use crate::animation::AnimationLibrary;
+use crate::bomber::{drop_bomb, spawn_bomber, Bomber, BomberTextures, ManualBombing, BOMBER_SPEED};
+use crate::consts::{GROUND_Y, WINDOW_WIDTH};
+use crate::rules::GameMode;
+use crate::score::GameClock;
+use crate::session::{GameSession, SessionCleanup};
+use crate::{AppState, BombDropEvent};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use std::time::Duration;
+
+const STARTING_BUDGET: f32 = 60.;
+const MAX_BUDGET: f32 = 150.;
+const BUDGET_REFILL_RATE: f32 = 6.; // points / s
+const AIRCRAFT_COST: f32 = 30.;
+const BOMBER_COST: f32 = 50.;
+
+const CURSOR_SPEED: f32 = 250.; // px / s
+const STICK_DEADZONE: f32 = 0.2;
+const DROP_ZONE_MAX_X: f32 = WINDOW_WIDTH / 2. - 40.;
+const CURSOR_X: f32 = WINDOW_WIDTH / 2. - 20.;
+
+/// The defender wins by holding out this long
+const MATCH_DURATION: Duration = Duration::from_secs(180);
+
+const ATTACKER_COLOR: Color = Color::ORANGE_RED;
+const FONT_SIZE: f32 = 24.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AttackerUnit {
+    Aircraft,
+    Bomber,
+}
+
+impl AttackerUnit {
+    fn cost(&self) -> f32 {
+        match self {
+            AttackerUnit::Aircraft => AIRCRAFT_COST,
+            AttackerUnit::Bomber => BOMBER_COST,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            AttackerUnit::Aircraft => "AIRCRAFT",
+            AttackerUnit::Bomber => "BOMBER",
+        }
+    }
+}
+
+/// The enemy commander's budget and launch selection
+#[derive(Resource)]
+pub struct Attacker {
+    pub budget: f32,
+    pub unit: AttackerUnit,
+    /// Launch from the left edge, heading right
+    pub from_left: bool,
+    pub altitude: f32,
+    /// Where the next aircraft's stick starts jumping
+    pub drop_x: f32,
+}
+
+impl Default for Attacker {
+    fn default() -> Attacker {
+        Attacker {
+            budget: STARTING_BUDGET,
+            unit: AttackerUnit::Aircraft,
+            from_left: true,
+            altitude: 0.5 * (SPAWN_Y_MIN + SPAWN_Y_MAX),
+            drop_x: -0.5 * DROP_ZONE_MAX_X,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VersusWinner {
+    Defender,
+    Attacker,
+}
+
+#[derive(Component)]
+struct AttackerText;
+
+#[derive(Component)]
+struct LaunchCursor;
+
+#[derive(Component)]
+struct DropZoneMarker;
+
+#[derive(Component)]
+struct WinnerText;
+
+#[derive(Resource)]
+struct VersusFont(Handle<Font>);
+
+fn load_font(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(VersusFont(asset_server.load("fonts/FiraSans-Bold.ttf")));
+}
+
+/// The attacker plays on the first connected gamepad
+fn attacker_gamepad(gamepads: &Gamepads) -> Option<Gamepad> {
+    gamepads.iter().min_by_key(|gamepad| gamepad.id)
+}
+
+fn setup_attacker(mut commands: Commands, font: Res<VersusFont>) {
+    commands.insert_resource(Attacker::default());
+    commands.remove_resource::<VersusWinner>();
+
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: FONT_SIZE,
+                    color: ATTACKER_COLOR,
+                },
+            ),
+            ..default()
+        })
+        .insert(AttackerText)
+        .insert(GameSession);
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: ATTACKER_COLOR,
+                custom_size: Some(Vec2::new(20., 6.)),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(LaunchCursor)
+        .insert(GameSession);
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: ATTACKER_COLOR.with_a(0.6),
+                custom_size: Some(Vec2::new(4., 20.)),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(DropZoneMarker)
+        .insert(GameSession);
+}
+
+/// Budget refills over time
+fn refill_budget(mut attacker: ResMut<Attacker>, time: Res<Time>) {
+    attacker.budget = (attacker.budget + BUDGET_REFILL_RATE * time.delta_seconds()).min(MAX_BUDGET);
+}
+
+/// Left stick aims altitude and drop zone, bumpers pick the side, North swaps units,
+/// South launches and East releases a bomb.
+#[allow(clippy::too_many_arguments)]
+fn attacker_input_system(
+    mut commands: Commands,
+    gamepads: Res<Gamepads>,
+    button_inputs: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    time: Res<Time>,
+    mut attacker: ResMut<Attacker>,
+    aircraft_textures: Res<AircraftTextures>,
+    bomber_textures: Res<BomberTextures>,
+    animations: Res<AnimationLibrary>,
+    mut bomber_query: Query<(&mut Bomber, &Transform, &Velocity), With<ManualBombing>>,
+    mut event_writer: EventWriter<BombDropEvent>,
+) {
+    let Some(gamepad) = attacker_gamepad(&gamepads) else {
+        return;
+    };
+    let pressed = |button_type: GamepadButtonType| {
+        button_inputs.pressed(GamepadButton::new(gamepad, button_type))
+    };
+    let just_pressed = |button_type: GamepadButtonType| {
+        button_inputs.just_pressed(GamepadButton::new(gamepad, button_type))
+    };
+    let axis = |axis_type: GamepadAxisType| {
+        let value = axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.);
+        if value.abs() < STICK_DEADZONE {
+            0.
+        } else {
+            value
+        }
+    };
+
+    // Altitude and drop zone cursors
+    let mut dy = axis(GamepadAxisType::LeftStickY);
+    if pressed(GamepadButtonType::DPadUp) {
+        dy += 1.;
+    }
+    if pressed(GamepadButtonType::DPadDown) {
+        dy -= 1.;
+    }
+    let mut dx = axis(GamepadAxisType::LeftStickX);
+    if pressed(GamepadButtonType::DPadRight) {
+        dx += 1.;
+    }
+    if pressed(GamepadButtonType::DPadLeft) {
+        dx -= 1.;
+    }
+    let step = CURSOR_SPEED * time.delta_seconds();
+    attacker.altitude = (attacker.altitude + dy * step).clamp(SPAWN_Y_MIN, SPAWN_Y_MAX);
+    attacker.drop_x = (attacker.drop_x + dx * step).clamp(-DROP_ZONE_MAX_X, DROP_ZONE_MAX_X);
+
+    if just_pressed(GamepadButtonType::LeftTrigger) {
+        attacker.from_left = true;
+    }
+    if just_pressed(GamepadButtonType::RightTrigger) {
+        attacker.from_left = false;
+    }
+    if just_pressed(GamepadButtonType::North) {
+        attacker.unit = match attacker.unit {
+            AttackerUnit::Aircraft => AttackerUnit::Bomber,
+            AttackerUnit::Bomber => AttackerUnit::Aircraft,
+        };
+    }
+
+    // Launch
+    if just_pressed(GamepadButtonType::South) && attacker.budget >= attacker.unit.cost() {
+        attacker.budget -= attacker.unit.cost();
+        match attacker.unit {
+            AttackerUnit::Aircraft => {
+                let aircraft = spawn_aircraft(
+                    &mut commands,
+                    &aircraft_textures,
+                    attacker.from_left,
+                    attacker.altitude,
+                    AIRCRAFT_SPEED,
+                    PARATROOPER_STICK_SIZE,
+                );
+                commands
+                    .entity(aircraft)
+                    .insert(DropZone::new(attacker.drop_x));
+            }
+            AttackerUnit::Bomber => {
+                let bomber = spawn_bomber(
+                    &mut commands,
+                    &bomber_textures,
+                    attacker.from_left,
+                    attacker.altitude,
+                    BOMBER_SPEED,
+                );
+                commands.entity(bomber).insert(ManualBombing);
+            }
+        }
+    }
+
+    // Release a bomb from the first bomber over the battlefield
+    if just_pressed(GamepadButtonType::East) {
+        if let Some((mut bomber, transform, velocity)) =
+            bomber_query.iter_mut().find(|(bomber, transform, _)| {
+                bomber.has_payload() && transform.translation.x.abs() < WINDOW_WIDTH / 2.
+            })
+        {
+            drop_bomb(&mut commands, &animations, &mut bomber, transform, velocity);
+            event_writer.send(BombDropEvent);
+        }
+    }
+}
+
+fn update_attacker_hud(
+    attacker: Res<Attacker>,
+    mut text_query: Query<&mut Text, With<AttackerText>>,
+    mut cursor_query: Query<&mut Transform, (With<LaunchCursor>, Without<DropZoneMarker>)>,
+    mut marker_query: Query<&mut Transform, (With<DropZoneMarker>, Without<LaunchCursor>)>,
+) {
+    let side = if attacker.from_left { "LEFT" } else { "RIGHT" };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "ATTACKER {:03}/{:03}  {} ({:.0})  {}",
+            attacker.budget.floor(),
+            MAX_BUDGET,
+            attacker.unit.name(),
+            attacker.unit.cost(),
+            side,
+        );
+    }
+    let cursor_x = if attacker.from_left {
+        -CURSOR_X
+    } else {
+        CURSOR_X
+    };
+    for mut transform in cursor_query.iter_mut() {
+        transform.translation = Vec3::new(cursor_x, attacker.altitude, 9.);
+    }
+    for mut transform in marker_query.iter_mut() {
+        transform.translation = Vec3::new(attacker.drop_x, GROUND_Y + 10., 9.);
+    }
+}
+
+/// The defender wins by surviving the match
+fn match_clock_system(
+    mut commands: Commands,
+    game_clock: Res<GameClock>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if game_clock.duration() >= MATCH_DURATION {
+        commands.insert_resource(VersusWinner::Defender);
+        next_state.set(AppState::GameOver);
+    }
+}
+
+/// Any other game over means the attacker got through
+fn show_winner(mut commands: Commands, winner: Option<Res<VersusWinner>>, font: Res<VersusFont>) {
+    let winner = winner.map_or(VersusWinner::Attacker, |winner| *winner);
+    commands.insert_resource(winner);
+    let message = match winner {
+        VersusWinner::Defender => "DEFENDER WINS",
+        VersusWinner::Attacker => "ATTACKER WINS",
+    };
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(50.0),
+                bottom: Val::Px(350.0),
+                ..default()
+            },
+            text: Text::from_section(
+                message,
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 75.0,
+                    color: ATTACKER_COLOR,
+                },
+            ),
+            ..default()
+        })
+        .insert(WinnerText)
+        .insert(GameSession);
+}
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_font)
+            .add_systems(
+                OnEnter(AppState::NewGame),
+                setup_attacker
+                    .run_if(resource_equals(GameMode::Versus))
+                    .after(SessionCleanup),
+            )
+            .add_systems(
+                Update,
+                (
+                    refill_budget,
+                    attacker_input_system,
+                    update_attacker_hud,
+                    match_clock_system,
+                )
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(resource_equals(GameMode::Versus)),
+            )
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                show_winner.run_if(resource_equals(GameMode::Versus)),
+            );
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::audio::{AudioSink, SpatialAudioSink};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Kinds of sound effect that share a voice budget
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundCategory {
+    Gunshot,
+    Explosion,
+    Scream,
+    Whistle,
+}
+
+impl SoundCategory {
+    const ALL: [SoundCategory; 4] = [
+        SoundCategory::Gunshot,
+        SoundCategory::Explosion,
+        SoundCategory::Scream,
+        SoundCategory::Whistle,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoundCategory::Gunshot => "Gunshot",
+            SoundCategory::Explosion => "Explosion",
+            SoundCategory::Scream => "Scream",
+            SoundCategory::Whistle => "Whistle",
+        }
+    }
+}
+
+/// Most sounds of each category allowed to play at once. Set from `GameSettings`.
+#[derive(Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceLimits {
+    pub gunshot: usize,
+    pub explosion: usize,
+    pub scream: usize,
+    pub whistle: usize,
+}
+
+impl Default for VoiceLimits {
+    fn default() -> VoiceLimits {
+        VoiceLimits {
+            gunshot: 4,
+            explosion: 6,
+            scream: 3,
+            whistle: 4,
+        }
+    }
+}
+
+impl VoiceLimits {
+    pub fn limit(&self, category: SoundCategory) -> usize {
+        match category {
+            SoundCategory::Gunshot => self.gunshot,
+            SoundCategory::Explosion => self.explosion,
+            SoundCategory::Scream => self.scream,
+            SoundCategory::Whistle => self.whistle,
+        }
+    }
+
+    pub fn limit_mut(&mut self, category: SoundCategory) -> &mut usize {
+        match category {
+            SoundCategory::Gunshot => &mut self.gunshot,
+            SoundCategory::Explosion => &mut self.explosion,
+            SoundCategory::Scream => &mut self.scream,
+            SoundCategory::Whistle => &mut self.whistle,
+        }
+    }
+}
+
+/// A sound effect counted against its category's limit. When a category is over its limit the
+/// lowest priority voices are stolen first, oldest first among equals.
+#[derive(Component)]
+pub struct Voice {
+    category: SoundCategory,
+    priority: u8,
+    /// Start order, assigned when the voice is first counted
+    order: u64,
+    /// Playing on an entity that outlives it, like a falling bomb, so stealing removes only the
+    /// sound
+    attached: bool,
+}
+
+impl Voice {
+    pub fn new(category: SoundCategory, priority: u8) -> Voice {
+        Voice {
+            category,
+            priority,
+            order: 0,
+            attached: false,
+        }
+    }
+
+    /// A voice playing on an entity that must survive the sound being stolen
+    pub fn attached(category: SoundCategory, priority: u8) -> Voice {
+        Voice {
+            attached: true,
+            ..Voice::new(category, priority)
+        }
+    }
+}
+
+/// Steal voices over the limits
+fn limit_voices(
+    mut commands: Commands,
+    limits: Res<VoiceLimits>,
+    mut next_order: Local<u64>,
+    mut voice_query: Query<(Entity, &mut Voice)>,
+) {
+    for (_, mut voice) in voice_query.iter_mut() {
+        if voice.is_added() {
+            *next_order += 1;
+            voice.order = *next_order;
+        }
+    }
+
+    for category in SoundCategory::ALL {
+        let mut voices: Vec<(Entity, &Voice)> = voice_query
+            .iter()
+            .filter(|(_, voice)| voice.category == category)
+            .collect();
+        let limit = limits.limit(category);
+        if voices.len() <= limit {
+            continue;
+        }
+        voices.sort_by_key(|(_, voice)| (voice.priority, voice.order));
+        for &(entity, voice) in &voices[..voices.len() - limit] {
+            if voice.attached {
+                commands.entity(entity).remove::<(
+                    Voice,
+                    PlaybackSettings,
+                    Handle<AudioSource>,
+                    AudioSink,
+                    SpatialAudioSink,
+                )>();
+            } else {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+pub struct VoicePlugin;
+
+impl Plugin for VoicePlugin {
+    fn build(&self, app: &mut App) {
+        // Unordered with playback: a stolen voice that already started is silenced when its
+        // sink is dropped
+        app.init_resource::<VoiceLimits>()
+            .add_systems(PostUpdate, limit_voices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voices_over_the_limit_are_stolen() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, VoicePlugin));
+        let limit = VoiceLimits::default().gunshot;
+        for _ in 0..limit + 2 {
+            app.world.spawn(Voice::new(SoundCategory::Gunshot, 0));
+        }
+        let scream = app.world.spawn(Voice::new(SoundCategory::Scream, 0)).id();
+        app.update();
+
+        let mut voice_query = app.world.query::<&Voice>();
+        let gunshots = voice_query
+            .iter(&app.world)
+            .filter(|voice| voice.category == SoundCategory::Gunshot)
+            .count();
+        assert_eq!(gunshots, limit);
+        assert!(app.world.get::<Voice>(scream).is_some());
+    }
+}
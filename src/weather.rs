@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_rapier2d::prelude::Velocity;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::FRAC_PI_2;
+
+use crate::consts::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::map::Map;
+use crate::paratrooper::{Paratrooper, ParatrooperState};
+use crate::particle::{spawn_burst, Effect, ParticleLibrary};
+use crate::session::GameSession;
+use crate::{AppState, WaveStartEvent};
+
+/// Fog banks draw over the planes and troopers, under the clouds
+const FOG_Z: f32 = 7.;
+const FOG_COLOR: Color = Color::rgba(0.75, 0.77, 0.8, 0.9);
+const FOG_TEXTURE_HEIGHT: usize = 64;
+/// How much of the wind speed an open parachute picks up
+const PARACHUTE_WIND_DRIFT: f32 = 0.5;
+
+/// Rain, snow or fog bank, and how it changes the fight
+#[derive(Clone, Copy, Debug)]
+pub struct WeatherData {
+    precipitation: Option<Effect>,
+    /// Particles per second across the width of the screen
+    precipitation_rate: f32,
+    /// Horizontal drift of the precipitation, in pixels per second
+    wind: f32,
+    /// Altitude bands, bottom and top, that fog hides
+    fog_banks: &'static [(f32, f32)],
+    /// Scales the drag and gravity on an open parachute
+    pub parachute_damping: f32,
+    pub parachute_gravity: f32,
+    /// Scales how fast clouds drift
+    pub cloud_speed: f32,
+}
+
+/// Current weather, picked from the map's list as each wave starts
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// Wet chutes come down faster. Clouds scud past.
+    Rain,
+    /// Troopers drift down slowly through still air
+    Snow,
+    /// Banks of fog hide aircraft flying at their altitude
+    Fog,
+}
+
+impl Weather {
+    pub fn data(&self) -> WeatherData {
+        let clear = WeatherData {
+            precipitation: None,
+            precipitation_rate: 0.,
+            wind: 0.,
+            fog_banks: &[],
+            parachute_damping: 1.,
+            parachute_gravity: 1.,
+            cloud_speed: 1.,
+        };
+        match self {
+            Weather::Clear => clear,
+            Weather::Rain => WeatherData {
+                precipitation: Some(Effect::Rain),
+                precipitation_rate: 250.,
+                wind: 120.,
+                parachute_damping: 0.8,
+                parachute_gravity: 1.3,
+                cloud_speed: 2.,
+                ..clear
+            },
+            Weather::Snow => WeatherData {
+                precipitation: Some(Effect::Snow),
+                precipitation_rate: 60.,
+                wind: 20.,
+                parachute_damping: 1.3,
+                parachute_gravity: 0.8,
+                cloud_speed: 0.6,
+                ..clear
+            },
+            Weather::Fog => WeatherData {
+                fog_banks: &[(60., 170.), (250., 330.)],
+                cloud_speed: 0.3,
+                ..clear
+            },
+        }
+    }
+}
+
+/// Wind across the battlefield in pixels per second, positive blowing right, on top of the
+/// weather's own. Set by scenarios.
+#[derive(Resource, Default)]
+pub struct Wind(pub f32);
+
+/// A band of fog across the sky
+#[derive(Component)]
+struct FogBank;
+
+#[derive(Resource)]
+struct WeatherTextures {
+    fog: Handle<Image>,
+}
+
+/// Thickest through the middle, thinning out at the top and bottom
+fn fog_image() -> Image {
+    let mut data = Vec::with_capacity(FOG_TEXTURE_HEIGHT * 4);
+    for y in 0..FOG_TEXTURE_HEIGHT {
+        let offset = (y as f32 + 0.5) / FOG_TEXTURE_HEIGHT as f32 * 2. - 1.;
+        let alpha = (1. - offset * offset).clamp(0., 1.);
+        data.extend_from_slice(&[255, 255, 255, (alpha * 255.) as u8]);
+    }
+    Image::new(
+        Extent3d {
+            width: 1,
+            height: FOG_TEXTURE_HEIGHT as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn setup_weather(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    commands.insert_resource(WeatherTextures {
+        fog: images.add(fog_image()),
+    });
+}
+
+fn reset_weather(mut weather: ResMut<Weather>, mut wind: ResMut<Wind>) {
+    *weather = Weather::default();
+    *wind = Wind::default();
+}
+
+/// Each map runs through its own weather, wave by wave
+fn change_weather(
+    map: Res<Map>,
+    mut weather: ResMut<Weather>,
+    mut event_reader: EventReader<WaveStartEvent>,
+) {
+    if let Some(WaveStartEvent(wave)) = event_reader.read().last() {
+        let forecast = map.data().weather;
+        let next = forecast[(*wave as usize - 1) % forecast.len()];
+        if *weather != next {
+            *weather = next;
+        }
+    }
+}
+
+/// Roll the fog banks in and out as the weather changes
+fn update_fog(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    textures: Res<WeatherTextures>,
+    fog_query: Query<Entity, With<FogBank>>,
+) {
+    if !weather.is_changed() {
+        return;
+    }
+    for entity in fog_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for &(bottom, top) in weather.data().fog_banks {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: FOG_COLOR,
+                    custom_size: Some(Vec2::new(WINDOW_WIDTH + 100., top - bottom)),
+                    ..default()
+                },
+                texture: textures.fog.clone(),
+                transform: Transform::from_xyz(0., (bottom + top) / 2., FOG_Z),
+                ..default()
+            })
+            .insert(FogBank)
+            .insert(GameSession);
+    }
+}
+
+/// Rain and snow fall in from above the top of the screen
+fn precipitation_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    wind: Res<Wind>,
+    particles: Res<ParticleLibrary>,
+    mut pending: Local<f32>,
+) {
+    let mut data = weather.data();
+    data.wind += wind.0;
+    let Some(effect) = data.precipitation else {
+        *pending = 0.;
+        return;
+    };
+    *pending += data.precipitation_rate * time.delta_seconds();
+    let mut rng = rand::thread_rng();
+    // Slanted by the wind, so start some off the upwind edge
+    let direction = -FRAC_PI_2 + data.wind / 800.;
+    let half_width = WINDOW_WIDTH / 2. + data.wind.abs();
+    while *pending >= 1. {
+        *pending -= 1.;
+        let x = rng.gen_range(-half_width..half_width) - data.wind;
+        let position = Vec2::new(x, WINDOW_HEIGHT / 2. + 20.);
+        spawn_burst(&mut commands, &particles, effect, position, direction);
+    }
+}
+
+/// Open parachutes drift with the wind
+fn wind_drift_system(wind: Res<Wind>, mut paratrooper_query: Query<(&Paratrooper, &mut Velocity)>) {
+    for (paratrooper, mut velocity) in paratrooper_query.iter_mut() {
+        if paratrooper.state == ParatrooperState::Floating {
+            velocity.linvel.x = PARACHUTE_WIND_DRIFT * wind.0;
+        }
+    }
+}
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weather>()
+            .init_resource::<Wind>()
+            .add_systems(Startup, setup_weather)
+            .add_systems(OnEnter(AppState::NewGame), reset_weather)
+            .add_systems(
+                Update,
+                (
+                    change_weather,
+                    update_fog,
+                    precipitation_system,
+                    wind_drift_system,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}